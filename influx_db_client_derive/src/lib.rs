@@ -0,0 +1,222 @@
+//! # InfluxDB Client derive
+//!
+//! `#[derive(InfluxPoint)]` generates `From<T> for Point` so a metric struct can be
+//! written without building the point by hand. Use it through the `derive` feature
+//! of `influx_db_client`.
+//!
+//! ```Rust
+//! use influx_db_client::{InfluxPoint, Point};
+//!
+//! #[derive(InfluxPoint)]
+//! #[influx(measurement = "cpu")]
+//! struct Cpu {
+//!     #[influx(tag)]
+//!     host: String,
+//!     #[influx(tag, rename = "dc")]
+//!     datacenter: Option<String>,
+//!     usage: f64,
+//!     #[influx(field, rename = "load")]
+//!     load_average: f64,
+//!     #[influx(skip)]
+//!     sampled_by: String,
+//!     #[influx(timestamp)]
+//!     time: i64,
+//! }
+//!
+//! let points = metrics.into_iter().map(Point::from);
+//! client.write_points(points, None, None).await?;
+//! ```
+//!
+//! Fields without an attribute are written as fields. `Option` tags and fields are
+//! left out of the point when `None`. The measurement defaults to the struct name.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+    parse_macro_input, spanned::Spanned, Data, DeriveInput, Error, Fields, GenericParam, Lifetime,
+    LitStr, Type,
+};
+
+/// Derive `From<T> for influx_db_client::Point`, see the crate documentation
+#[proc_macro_derive(InfluxPoint, attributes(influx))]
+pub fn derive_influx_point(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+enum Kind {
+    Tag,
+    Field,
+    Timestamp,
+    Skip,
+}
+
+struct Member {
+    ident: syn::Ident,
+    name: String,
+    kind: Kind,
+    optional: bool,
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    input.span(),
+                    "InfluxPoint can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "InfluxPoint can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut measurement = input.ident.to_string();
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("influx")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("measurement") {
+                measurement = meta.value()?.parse::<LitStr>()?.value();
+                Ok(())
+            } else {
+                Err(meta.error("expected `measurement = \"...\"`"))
+            }
+        })?;
+    }
+
+    let mut members = Vec::new();
+    for field in fields {
+        let ident = field.ident.clone().expect("named field");
+        let mut name = ident.to_string();
+        let mut kind = None;
+
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("influx")) {
+            attr.parse_nested_meta(|meta| {
+                let new_kind = if meta.path.is_ident("tag") {
+                    Kind::Tag
+                } else if meta.path.is_ident("field") {
+                    Kind::Field
+                } else if meta.path.is_ident("timestamp") {
+                    Kind::Timestamp
+                } else if meta.path.is_ident("skip") {
+                    Kind::Skip
+                } else if meta.path.is_ident("rename") {
+                    name = meta.value()?.parse::<LitStr>()?.value();
+                    return Ok(());
+                } else {
+                    return Err(meta.error(
+                        "expected one of `tag`, `field`, `timestamp`, `skip`, `rename = \"...\"`",
+                    ));
+                };
+                if kind.replace(new_kind).is_some() {
+                    return Err(
+                        meta.error("only one of `tag`, `field`, `timestamp`, `skip` is allowed")
+                    );
+                }
+                Ok(())
+            })?;
+        }
+
+        members.push(Member {
+            ident,
+            name,
+            kind: kind.unwrap_or(Kind::Field),
+            optional: is_option(&field.ty),
+        });
+    }
+
+    let timestamps: Vec<_> = members
+        .iter()
+        .filter(|m| matches!(m.kind, Kind::Timestamp))
+        .collect();
+    if timestamps.len() > 1 {
+        return Err(Error::new(
+            timestamps[1].ident.span(),
+            "only one field can be marked `#[influx(timestamp)]`",
+        ));
+    }
+    if !members.iter().any(|m| matches!(m.kind, Kind::Field)) {
+        return Err(Error::new(
+            input.ident.span(),
+            "InfluxPoint needs at least one field that is not a tag, timestamp or skipped",
+        ));
+    }
+
+    let statements = members.iter().filter_map(|member| {
+        let ident = &member.ident;
+        let name = &member.name;
+        let method = match member.kind {
+            Kind::Tag => quote!(add_tag(#name, v)),
+            Kind::Field => quote!(add_field(#name, v)),
+            Kind::Timestamp => quote!(add_timestamp(v)),
+            Kind::Skip => return None,
+        };
+        Some(if member.optional {
+            quote! {
+                if let ::core::option::Option::Some(v) = value.#ident {
+                    point = point.#method;
+                }
+            }
+        } else {
+            quote! {
+                let v = value.#ident;
+                point = point.#method;
+            }
+        })
+    });
+
+    // Every lifetime of the struct must outlive the lifetime of the point
+    let point_lifetime = Lifetime::new("'__influx_point", Span::call_site());
+    let mut impl_generics = input.generics.clone();
+    let outlives: Vec<_> = input
+        .generics
+        .lifetimes()
+        .map(|l| {
+            let l = &l.lifetime;
+            quote!(#l: #point_lifetime)
+        })
+        .collect();
+    impl_generics.params.insert(
+        0,
+        GenericParam::Lifetime(syn::LifetimeParam::new(point_lifetime.clone())),
+    );
+    let (impl_generics, _, _) = impl_generics.split_for_impl();
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+    let predicates = where_clause.into_iter().flat_map(|w| w.predicates.iter());
+
+    let ident = &input.ident;
+    Ok(quote! {
+        impl #impl_generics ::core::convert::From<#ident #ty_generics>
+            for ::influx_db_client::Point<#point_lifetime>
+        where
+            #(#predicates,)*
+            #(#outlives,)*
+        {
+            fn from(value: #ident #ty_generics) -> Self {
+                let mut point = ::influx_db_client::Point::new(#measurement);
+                #(#statements)*
+                point
+            }
+        }
+    })
+}
+
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .map(|s| s.ident == "Option")
+            .unwrap_or(false),
+        _ => false,
+    }
+}
@@ -0,0 +1,325 @@
+use futures::prelude::*;
+use std::{fmt, sync::Arc, time::Duration};
+use tokio::{
+    sync::{mpsc, oneshot, Semaphore},
+    task::JoinHandle,
+    time::{sleep, Instant},
+};
+
+use crate::{error, Client, Point, Precision};
+
+type ErrorHandler = Arc<dyn Fn(error::Error, usize) + Send + Sync>;
+
+/// Options of a `BatchWriter`
+#[derive(Clone)]
+pub struct BatchOptions {
+    max_points: usize,
+    max_bytes: usize,
+    flush_interval: Duration,
+    max_concurrent_flushes: usize,
+    queue_size: usize,
+    precision: Option<Precision>,
+    retention_policy: Option<String>,
+    on_error: Option<ErrorHandler>,
+}
+
+impl BatchOptions {
+    /// Flush at 5000 points, 1 MiB or 1 second, with up to 4 flushes in flight
+    pub fn new() -> Self {
+        BatchOptions {
+            max_points: 5000,
+            max_bytes: 1024 * 1024,
+            flush_interval: Duration::from_secs(1),
+            max_concurrent_flushes: 4,
+            queue_size: 10_000,
+            precision: None,
+            retention_policy: None,
+            on_error: None,
+        }
+    }
+
+    /// Flush once a batch holds this many points
+    pub fn set_max_points(mut self, max_points: usize) -> Self {
+        self.max_points = max_points.max(1);
+        self
+    }
+
+    /// Flush once a batch holds this many bytes of line protocol
+    pub fn set_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Flush a batch at the latest this long after its first point was queued
+    pub fn set_flush_interval(mut self, flush_interval: Duration) -> Self {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    /// Number of write requests that may be in flight at once.
+    ///
+    /// When all of them are busy, batching pauses and writers wait on the full queue.
+    pub fn set_max_concurrent_flushes(mut self, max_concurrent_flushes: usize) -> Self {
+        self.max_concurrent_flushes = max_concurrent_flushes.max(1);
+        self
+    }
+
+    /// Number of points that can be queued before `BatchHandle::write` waits
+    pub fn set_queue_size(mut self, queue_size: usize) -> Self {
+        self.queue_size = queue_size.max(1);
+        self
+    }
+
+    /// Precision of the writes, the client's default precision if not set
+    pub fn set_precision(mut self, precision: Precision) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    /// Retention policy of the writes, the client's default retention policy if not set
+    pub fn set_retention_policy<T>(mut self, rp: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.retention_policy = Some(rp.into());
+        self
+    }
+
+    /// Called with the error and the number of points lost whenever a batch fails to write,
+    /// or a point fails to encode. Failures are dropped silently if not set.
+    pub fn set_error_handler<F>(mut self, on_error: F) -> Self
+    where
+        F: Fn(error::Error, usize) + Send + Sync + 'static,
+    {
+        self.on_error = Some(Arc::new(on_error));
+        self
+    }
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for BatchOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BatchOptions")
+            .field("max_points", &self.max_points)
+            .field("max_bytes", &self.max_bytes)
+            .field("flush_interval", &self.flush_interval)
+            .field("max_concurrent_flushes", &self.max_concurrent_flushes)
+            .field("queue_size", &self.queue_size)
+            .field("precision", &self.precision)
+            .field("retention_policy", &self.retention_policy)
+            .field("on_error", &self.on_error.is_some())
+            .finish()
+    }
+}
+
+/// Cheap, cloneable handle queueing points into a `BatchWriter`
+#[derive(Debug, Clone)]
+pub struct BatchHandle {
+    sender: mpsc::Sender<Point<'static>>,
+}
+
+impl BatchHandle {
+    /// Queue a point, waiting while the queue is full.
+    /// Fails once the writer is closed.
+    pub fn write(&self, point: Point<'static>) -> impl Future<Output = Result<(), error::Error>> {
+        let sender = self.sender.clone();
+        async move {
+            sender
+                .send(point)
+                .await
+                .map_err(|_| error::Error::BatchWriterClosed)
+        }
+    }
+
+    /// Queue a point without waiting, failing if the queue is full or the writer is closed
+    pub fn try_write(&self, point: Point<'static>) -> Result<(), error::Error> {
+        self.sender.try_send(point).map_err(|e| match e {
+            mpsc::error::TrySendError::Full(_) => error::Error::BatchQueueFull,
+            mpsc::error::TrySendError::Closed(_) => error::Error::BatchWriterClosed,
+        })
+    }
+}
+
+/// Background writer grouping points into batched write requests.
+///
+/// Points are queued through `BatchHandle`s and written by a task on the current tokio
+/// runtime once a batch reaches the point or byte limit, or its flush interval elapses.
+///
+/// ```Rust
+/// use influx_db_client::{batch::{BatchOptions, BatchWriter}, Client, Point};
+///
+/// let writer = BatchWriter::new(
+///     Client::default(),
+///     BatchOptions::new().set_error_handler(|err, points| eprintln!("lost {} points: {}", points, err)),
+/// );
+/// let handle = writer.handle();
+///
+/// handle.write(Point::new("cpu").add_field("usage", 0.5)).await?;
+///
+/// // write everything still queued before returning
+/// writer.close().await;
+/// ```
+#[derive(Debug)]
+pub struct BatchWriter {
+    handle: BatchHandle,
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl BatchWriter {
+    /// Start a batch writer on the current tokio runtime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime.
+    pub fn new(client: Client, options: BatchOptions) -> Self {
+        let (sender, receiver) = mpsc::channel(options.queue_size);
+        let (shutdown, shutdown_receiver) = oneshot::channel();
+        let task = tokio::spawn(run(client, options, receiver, shutdown_receiver));
+
+        BatchWriter {
+            handle: BatchHandle { sender },
+            shutdown,
+            task,
+        }
+    }
+
+    /// A new handle to queue points with
+    pub fn handle(&self) -> BatchHandle {
+        self.handle.clone()
+    }
+
+    /// Queue a point, see `BatchHandle::write`
+    pub fn write(&self, point: Point<'static>) -> impl Future<Output = Result<(), error::Error>> {
+        self.handle.write(point)
+    }
+
+    /// Stop accepting points, write everything queued and wait for all writes to finish.
+    ///
+    /// Handles keep working when the writer is dropped without being closed,
+    /// the queue is drained once the writer and all its handles are dropped, without waiting.
+    pub fn close(self) -> impl Future<Output = ()> {
+        let _ = self.shutdown.send(());
+        self.task.map(|_| ())
+    }
+}
+
+struct Batch {
+    client: Client,
+    precision: Precision,
+    retention_policy: Option<String>,
+    on_error: Option<ErrorHandler>,
+    flushes: Arc<Semaphore>,
+    body: Vec<u8>,
+    points: usize,
+}
+
+impl Batch {
+    fn report(&self, err: error::Error, points: usize) {
+        if let Some(ref on_error) = self.on_error {
+            on_error(err, points)
+        }
+    }
+
+    /// Send the current batch, waiting for a free flush slot first
+    async fn flush(&mut self) {
+        if self.points == 0 {
+            return;
+        }
+
+        let permit = self
+            .flushes
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed");
+        let body = std::mem::take(&mut self.body);
+        let points = std::mem::replace(&mut self.points, 0);
+        let write = self.client.write_line_protocol(
+            body,
+            self.precision,
+            self.retention_policy.as_deref(),
+            self.client.get_compression(),
+        );
+        let on_error = self.on_error.clone();
+
+        tokio::spawn(async move {
+            if let Err(err) = write.await {
+                if let Some(ref on_error) = on_error {
+                    on_error(err, points)
+                }
+            }
+            // Release the handler before the slot, a closed writer keeps no reference to it
+            drop(on_error);
+            drop(permit);
+        });
+    }
+}
+
+async fn run(
+    client: Client,
+    options: BatchOptions,
+    mut receiver: mpsc::Receiver<Point<'static>>,
+    mut shutdown: oneshot::Receiver<()>,
+) {
+    let precision = options.precision.unwrap_or_else(|| client.get_precision());
+    let encoder = client.encoder(precision);
+    let max_concurrent_flushes = options.max_concurrent_flushes;
+    let mut batch = Batch {
+        client,
+        precision,
+        retention_policy: options.retention_policy,
+        on_error: options.on_error,
+        flushes: Arc::new(Semaphore::new(max_concurrent_flushes)),
+        body: Vec::new(),
+        points: 0,
+    };
+
+    let deadline = sleep(options.flush_interval);
+    tokio::pin!(deadline);
+    let mut shutting_down = false;
+
+    loop {
+        tokio::select! {
+            point = receiver.recv() => match point {
+                Some(point) => {
+                    if let Err(err) = encoder.encode_point(&point, &mut batch.body) {
+                        batch.report(err, 1);
+                        continue;
+                    }
+                    batch.points += 1;
+                    if batch.points == 1 {
+                        deadline
+                            .as_mut()
+                            .reset(Instant::now() + options.flush_interval);
+                    }
+                    if batch.points >= options.max_points || batch.body.len() >= options.max_bytes {
+                        batch.flush().await;
+                    }
+                }
+                None => break,
+            },
+            _ = &mut deadline, if batch.points > 0 => batch.flush().await,
+            closed = &mut shutdown, if !shutting_down => {
+                shutting_down = true;
+                // Refuse new points, then drain what is already queued.
+                // A writer dropped without `close` leaves its handles working.
+                if closed.is_ok() {
+                    receiver.close();
+                }
+            }
+        }
+    }
+
+    batch.flush().await;
+    // Every in-flight flush holds a permit until its write is done
+    let _ = batch
+        .flushes
+        .acquire_many(max_concurrent_flushes as u32)
+        .await;
+}
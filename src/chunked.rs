@@ -0,0 +1,372 @@
+use bytes::Bytes;
+use futures::{
+    prelude::*,
+    stream::{self, BoxStream},
+    task::{Context, Poll},
+};
+use serde::de::DeserializeOwned;
+use std::{collections::VecDeque, fmt, pin::Pin};
+
+use crate::{error::Error, Query, Series};
+
+/// Stream of the responses of a chunked query, decoded as they arrive.
+///
+/// The server sends one `Query` per chunk. Results and series split over several chunks
+/// are marked `partial` in all but their last chunk.
+///
+/// ```Rust
+/// use futures::prelude::*;
+///
+/// let mut chunks = client.query_chunked("select * from cpu", None, Some(1000)).await?;
+/// while let Some(chunk) = chunks.try_next().await? {
+///     for node in chunk.into_statements() {
+///         println!("{:?}", node?.series);
+///     }
+/// }
+/// ```
+pub struct ChunkedQuery {
+    stream: BoxStream<'static, Result<Query, Error>>,
+}
+
+/// Decoding state: the remaining body, the bytes received but not decoded yet,
+/// how many of them are known to hold no newline, and whether the stream is done
+type State<S> = (Pin<Box<S>>, Vec<u8>, usize, bool);
+
+impl ChunkedQuery {
+    /// Decode a body received in arbitrary pieces.
+    ///
+    /// The server ends every chunk with a newline, so only complete lines are decoded
+    /// and each received byte is scanned once.
+    pub(crate) fn new<S>(body: S) -> Self
+    where
+        S: Stream<Item = Result<Bytes, Error>> + Send + 'static,
+    {
+        let state: State<S> = (Box::pin(body), Vec::new(), 0, false);
+        let stream = stream::unfold(
+            state,
+            |(mut body, mut buffer, mut scanned, done)| async move {
+                if done {
+                    return None;
+                }
+
+                loop {
+                    if let Some(end) = buffer[scanned..].iter().position(|&b| b == b'\n') {
+                        let end = scanned + end;
+                        let query = decode_line(&buffer[..end]).map_err(Error::from);
+                        buffer.drain(..=end);
+                        scanned = 0;
+                        match query {
+                            Ok(Some(query)) => {
+                                return Some((Ok(query), (body, buffer, scanned, false)))
+                            }
+                            Ok(None) => continue,
+                            Err(err) => return Some((Err(err), (body, buffer, scanned, true))),
+                        }
+                    }
+                    scanned = buffer.len();
+
+                    match body.next().await {
+                        Some(Ok(bytes)) => buffer.extend_from_slice(&bytes),
+                        Some(Err(err)) => return Some((Err(err), (body, buffer, scanned, true))),
+                        // The last chunk may lack its newline
+                        None => {
+                            return match decode_line(&buffer) {
+                                Ok(None) => None,
+                                Ok(Some(query)) => Some((Ok(query), (body, Vec::new(), 0, true))),
+                                Err(err) => {
+                                    let err = if err.is_eof() {
+                                        Error::DeserializeError(
+                                            "The chunked response ended in the middle of a chunk"
+                                                .to_string(),
+                                        )
+                                    } else {
+                                        err.into()
+                                    };
+                                    Some((Err(err), (body, buffer, scanned, true)))
+                                }
+                            };
+                        }
+                    }
+                }
+            },
+        );
+
+        ChunkedQuery {
+            stream: stream.boxed(),
+        }
+    }
+
+    /// Merge the chunks of every series into the complete `Series`, with the id
+    /// of the statement it belongs to.
+    ///
+    /// Each series is yielded once its last chunk arrived, a failed statement
+    /// is yielded as its error.
+    ///
+    /// ```Rust
+    /// use futures::prelude::*;
+    ///
+    /// let series: Vec<(u64, Series)> = client
+    ///     .query_chunked("select * from cpu group by host", None, Some(1000))
+    ///     .await?
+    ///     .series()
+    ///     .try_collect()
+    ///     .await?;
+    /// ```
+    pub fn series(self) -> impl Stream<Item = Result<(u64, Series), Error>> + Send {
+        let state = (self, None, VecDeque::new(), false);
+        stream::unfold(
+            state,
+            |(mut chunks, mut pending, mut ready, mut done)| async move {
+                loop {
+                    if let Some(series) = ready.pop_front() {
+                        return Some((series, (chunks, pending, ready, done)));
+                    }
+                    if done {
+                        return None;
+                    }
+
+                    match chunks.next().await {
+                        Some(Ok(query)) => {
+                            for node in query.into_statements() {
+                                match node {
+                                    Ok(node) => {
+                                        let statement_id = node.statement_id.unwrap_or_default();
+                                        for series in node.series.unwrap_or_default() {
+                                            merge(&mut pending, &mut ready, statement_id, series);
+                                        }
+                                    }
+                                    Err(err) => {
+                                        ready.extend(pending.take().map(Ok));
+                                        ready.push_back(Err(err));
+                                    }
+                                }
+                            }
+                        }
+                        Some(Err(err)) => {
+                            ready.extend(pending.take().map(Ok));
+                            ready.push_back(Err(err));
+                        }
+                        None => {
+                            ready.extend(pending.take().map(Ok));
+                            done = true;
+                        }
+                    }
+                }
+            },
+        )
+    }
+
+    /// Deserialize every row of every series into `T`, as the chunks arrive.
+    ///
+    /// See `Series::into_rows` for how rows are mapped, a failed statement
+    /// is yielded as its error.
+    pub fn rows<T>(self) -> impl Stream<Item = Result<T, Error>> + Send
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        self.flat_map(|query| stream::iter(chunk_rows(query)))
+    }
+
+    /// Decode the body of a response
+    pub(crate) fn from_response(response: reqwest::Response) -> Self {
+        ChunkedQuery::new(stream::unfold(response, |mut response| async move {
+            match response.chunk().await {
+                Ok(Some(bytes)) => Some((Ok(bytes), response)),
+                Ok(None) => None,
+                Err(err) => Some((Err(err.into()), response)),
+            }
+        }))
+    }
+}
+
+/// Add the chunk of a series to the one being merged, moving complete series to `ready`.
+/// Chunks belong to the same series if they share statement id, name and tags.
+fn merge(
+    pending: &mut Option<(u64, Series)>,
+    ready: &mut VecDeque<Result<(u64, Series), Error>>,
+    statement_id: u64,
+    series: Series,
+) {
+    let series = match pending.take() {
+        Some((id, mut merged))
+            if id == statement_id && merged.name == series.name && merged.tags == series.tags =>
+        {
+            if let Some(values) = series.values {
+                merged.values.get_or_insert_with(Vec::new).extend(values);
+            }
+            merged.partial = series.partial;
+            merged
+        }
+        Some(merged) => {
+            ready.push_back(Ok(merged));
+            series
+        }
+        None => series,
+    };
+
+    if series.partial {
+        *pending = Some((statement_id, series));
+    } else {
+        ready.push_back(Ok((statement_id, series)));
+    }
+}
+
+/// The rows of all series of a chunk, and the errors of its failed statements
+fn chunk_rows<T: DeserializeOwned>(query: Result<Query, Error>) -> Vec<Result<T, Error>> {
+    let statements = match query {
+        Ok(query) => query.into_statements(),
+        Err(err) => return vec![Err(err)],
+    };
+
+    let mut rows = Vec::new();
+    for node in statements {
+        match node {
+            Ok(node) => {
+                for series in node.series.unwrap_or_default() {
+                    match series.into_rows() {
+                        Ok(series_rows) => rows.extend(series_rows.into_iter().map(Ok)),
+                        Err(err) => rows.push(Err(err)),
+                    }
+                }
+            }
+            Err(err) => rows.push(Err(err)),
+        }
+    }
+    rows
+}
+
+/// Decode one chunk, `None` for a blank line
+fn decode_line(line: &[u8]) -> Result<Option<Query>, serde_json::Error> {
+    if line.iter().all(u8::is_ascii_whitespace) {
+        return Ok(None);
+    }
+
+    serde_json::from_slice(line).map(Some)
+}
+
+impl Stream for ChunkedQuery {
+    type Item = Result<Query, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.stream.as_mut().poll_next(cx)
+    }
+}
+
+impl fmt::Debug for ChunkedQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ChunkedQuery").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ChunkedQuery;
+    use crate::error::Error;
+    use bytes::Bytes;
+    use futures::{executor::block_on, prelude::*, stream};
+
+    fn chunks(pieces: &[&'static str]) -> ChunkedQuery {
+        ChunkedQuery::new(stream::iter(
+            pieces
+                .iter()
+                .map(|piece| Ok(Bytes::from_static(piece.as_bytes())))
+                .collect::<Vec<_>>(),
+        ))
+    }
+
+    fn decode(pieces: &[&'static str]) -> Vec<Result<crate::Query, Error>> {
+        block_on(chunks(pieces).collect())
+    }
+
+    #[test]
+    fn decode_split_chunks() {
+        let queries = decode(&[
+            "{\"results\":[{\"statement_id\":0,\"series\":[{\"name\":\"cpu\",\"columns\":[\"time\"],\"values\":[[1]],\"partial\":true}],\"partial\":true}]}\n{\"res",
+            "ults\":[{\"statement_id\":0,\"series\":[{\"name\":\"cpu\",\"columns\":[\"time\"],\"val",
+            "ues\":[[2]]}]}]}\n",
+            "\n",
+        ]);
+
+        assert_eq!(queries.len(), 2);
+        let first = queries[0].as_ref().unwrap().results.as_ref().unwrap();
+        assert!(first[0].partial);
+        let second = queries[1].as_ref().unwrap().results.as_ref().unwrap();
+        assert!(!second[0].partial);
+        assert_eq!(
+            second[0].series.as_ref().unwrap()[0].values,
+            Some(vec![vec![serde_json::json!(2)]])
+        );
+    }
+
+    #[test]
+    fn merge_series() {
+        let pieces = [
+            "{\"results\":[{\"statement_id\":0,\"series\":[{\"name\":\"cpu\",\"tags\":{\"host\":\"a\"},\"columns\":[\"time\",\"value\"],\"values\":[[1,1],[2,2]],\"partial\":true}],\"partial\":true}]}\n",
+            "{\"results\":[{\"statement_id\":0,\"series\":[{\"name\":\"cpu\",\"tags\":{\"host\":\"a\"},\"columns\":[\"time\",\"value\"],\"values\":[[3,3]]},{\"name\":\"cpu\",\"tags\":{\"host\":\"b\"},\"columns\":[\"time\",\"value\"],\"values\":[[1,4]],\"partial\":true}],\"partial\":true}]}\n",
+            "{\"results\":[{\"statement_id\":0,\"series\":[{\"name\":\"cpu\",\"tags\":{\"host\":\"b\"},\"columns\":[\"time\",\"value\"],\"values\":[[2,5]]}]}]}\n",
+            "{\"results\":[{\"statement_id\":1,\"error\":\"database not found: foo\"}]}\n",
+        ];
+
+        let series: Vec<_> = block_on(chunks(&pieces).series().collect());
+        assert_eq!(series.len(), 3);
+        let (statement_id, a) = series[0].as_ref().unwrap();
+        assert_eq!(*statement_id, 0);
+        assert_eq!(a.tags.as_ref().unwrap()["host"], "a");
+        assert_eq!(a.values.as_ref().unwrap().len(), 3);
+        assert!(!a.partial);
+        let (_, b) = series[1].as_ref().unwrap();
+        assert_eq!(b.tags.as_ref().unwrap()["host"], "b");
+        assert_eq!(b.values.as_ref().unwrap().len(), 2);
+        assert!(matches!(series[2], Err(Error::DataBaseDoesNotExist { .. })));
+
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Row {
+            time: i64,
+            host: String,
+            value: i64,
+        }
+        let rows: Vec<_> = block_on(chunks(&pieces).rows::<Row>().collect());
+        assert_eq!(rows.len(), 6);
+        assert_eq!(
+            rows[3].as_ref().unwrap(),
+            &Row {
+                time: 1,
+                host: "b".to_string(),
+                value: 4
+            }
+        );
+        assert!(rows[5].is_err());
+    }
+
+    #[test]
+    fn merge_series_by_statement() {
+        // statement 0 stops while its series is partial, statement 1 has a series of the same name
+        let pieces = [
+            "{\"results\":[{\"statement_id\":0,\"series\":[{\"name\":\"cpu\",\"columns\":[\"time\"],\"values\":[[1]],\"partial\":true}],\"partial\":true}]}\n",
+            "{\"results\":[{\"statement_id\":1,\"series\":[{\"name\":\"cpu\",\"columns\":[\"time\"],\"values\":[[2]]}]}]}\n",
+        ];
+
+        let series: Vec<_> = block_on(chunks(&pieces).series().try_collect()).unwrap();
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[0].0, 0);
+        assert_eq!(series[0].1.values, Some(vec![vec![serde_json::json!(1)]]));
+        assert_eq!(series[1].0, 1);
+        assert_eq!(series[1].1.values, Some(vec![vec![serde_json::json!(2)]]));
+    }
+
+    #[test]
+    fn decode_errors() {
+        let queries = decode(&["{\"results\":[]}\n{\"results\":"]);
+        assert_eq!(queries.len(), 2);
+        assert!(queries[0].is_ok());
+        assert!(matches!(queries[1], Err(Error::DeserializeError(_))));
+
+        let queries = decode(&["{\"results\":[]}\n{\"results\":[]}"]);
+        assert_eq!(queries.len(), 2);
+        assert!(queries[1].is_ok());
+
+        let queries = decode(&["{\"results\":[]}\nnot json\n{\"results\":[]}\n"]);
+        assert_eq!(queries.len(), 2);
+        assert!(matches!(queries[1], Err(Error::DeserializeError(_))));
+    }
+}
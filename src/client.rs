@@ -1,16 +1,52 @@
-use bytes::Bytes;
 use futures::prelude::*;
-use reqwest::{Client as HttpClient, Response, Url};
-use serde_json::de::IoRead;
+use reqwest::{
+    header::{AUTHORIZATION, CONTENT_ENCODING},
+    Client as HttpClient, Response, Url,
+};
+use serde::{de::DeserializeOwned, Deserialize};
 use std::{
     borrow::Borrow,
-    io::Cursor,
+    collections::BTreeMap,
+    fmt,
     iter::FromIterator,
     net::UdpSocket,
     net::{SocketAddr, ToSocketAddrs},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    error::{self, RequestKind},
+    influxql::{Condition, InfluxDuration},
+    retry::{self, Reply},
+    schema::{ContinuousQuery, FieldKey, Privilege, RetentionPolicy, RunningQuery, UserInfo},
+    serialization, ChunkedQuery, LineProtocolEncoder, Node, Point, Points, Precision, Query,
+    QueryParam, RetryPolicy,
 };
 
-use crate::{error, serialization, ChunkedQuery, Node, Point, Points, Precision, Query};
+/// Compression of write request bodies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    /// Send plain line protocol
+    #[default]
+    None,
+    /// Gzip bodies of at least `min_size` bytes, smaller ones are sent as plain text
+    Gzip {
+        /// compression level from 0 (none) to 9 (best)
+        level: u32,
+        /// bodies smaller than this many bytes are not compressed
+        min_size: usize,
+    },
+}
+
+impl Compression {
+    /// Gzip at the default level 6 for bodies of at least 1 KiB
+    pub fn gzip() -> Self {
+        Compression::Gzip {
+            level: 6,
+            min_size: 1024,
+        }
+    }
+}
 
 /// The client to influxdb
 #[derive(Debug, Clone)]
@@ -20,6 +56,12 @@ pub struct Client {
     authentication: Option<(String, String)>,
     jwt_token: Option<String>,
     client: HttpClient,
+    encoder: LineProtocolEncoder,
+    compression: Compression,
+    precision: Precision,
+    retention_policy: Option<String>,
+    retry: RetryPolicy,
+    query_timeout: Option<Duration>,
 }
 
 impl Client {
@@ -34,6 +76,12 @@ impl Client {
             authentication: None,
             jwt_token: None,
             client: HttpClient::default(),
+            encoder: LineProtocolEncoder::new(),
+            compression: Compression::None,
+            precision: Precision::Seconds,
+            retention_policy: None,
+            retry: RetryPolicy::never(),
+            query_timeout: None,
         }
     }
 
@@ -48,6 +96,12 @@ impl Client {
             authentication: None,
             jwt_token: None,
             client,
+            encoder: LineProtocolEncoder::new(),
+            compression: Compression::None,
+            precision: Precision::Seconds,
+            retention_policy: None,
+            retry: RetryPolicy::never(),
+            query_timeout: None,
         }
     }
 
@@ -77,11 +131,104 @@ impl Client {
         self
     }
 
+    /// Validate every point before writing, see `Point::validate`.
+    ///
+    /// In strict mode an invalid point fails the whole write with `Error::InvalidPoint`
+    /// and nothing is sent to the server.
+    pub fn set_strict_validation(mut self, strict: bool) -> Self {
+        self.encoder = self.encoder.set_validation(strict);
+        self
+    }
+
+    /// Write fields sorted by key as well as tags, giving canonical lines
+    pub fn set_sort_fields(mut self, sort_fields: bool) -> Self {
+        self.encoder = self.encoder.set_sort_fields(sort_fields);
+        self
+    }
+
+    /// Set the compression of write bodies, `Compression::None` by default
+    pub fn set_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Set the precision used by writes that don't pass one, `Precision::Seconds` by default
+    pub fn set_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Set the retention policy used by writes that don't pass one,
+    /// by default the database's default retention policy
+    pub fn set_retention_policy<T>(mut self, rp: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.retention_policy = Some(rp.into());
+        self
+    }
+
+    /// Set the retrying of writes and queries, `RetryPolicy::never()` by default
+    pub fn set_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Give up on `query`, `query_as` and `query_with_params` after `timeout`, and kill
+    /// the query on the server when it times out or its future is dropped after being polled.
+    ///
+    /// The server doesn't tell the id of a query, so it is looked up in `SHOW QUERIES`
+    /// by database and text among the queries that started after the request was sent.
+    /// The texts are compared token by token with the parameters bound, the way the server
+    /// reformats them: ignoring case, whitespace and double quotes, floats rounded to
+    /// 3 decimals and durations by value, so `0.5` matches `0.500` and `60m` matches `1h`.
+    /// It is killed only if exactly one matches. The kill is best effort and its failure
+    /// is not reported: a query the server reformats otherwise, or one that is ambiguous,
+    /// keeps running after the timeout until it completes or `kill_query` stops it.
+    /// An identical query of another client that started meanwhile can still be killed
+    /// if this one never reached the server, keep the timeout well above the connect time.
+    ///
+    /// Both the timeout and the kill need a tokio runtime.
+    pub fn set_query_timeout(mut self, timeout: Duration) -> Self {
+        self.query_timeout = Some(timeout);
+        self
+    }
+
     /// View the current db name
     pub fn get_db(&self) -> &str {
         self.db.as_str()
     }
 
+    /// View the default write precision
+    pub fn get_precision(&self) -> Precision {
+        self.precision
+    }
+
+    /// View the default write retention policy
+    pub fn get_retention_policy(&self) -> Option<&str> {
+        self.retention_policy.as_deref()
+    }
+
+    /// View the default write compression
+    pub fn get_compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// View the retry policy of writes and queries
+    pub fn get_retry_policy(&self) -> RetryPolicy {
+        self.retry
+    }
+
+    /// View the timeout of `query`, none by default
+    pub fn get_query_timeout(&self) -> Option<Duration> {
+        self.query_timeout
+    }
+
+    /// The encoder writes of this client use, in the given precision
+    pub(crate) fn encoder(&self, precision: Precision) -> LineProtocolEncoder {
+        self.encoder.clone().set_precision(precision)
+    }
+
     /// Query whether the corresponding database exists, return bool
     pub fn ping(&self) -> impl Future<Output = bool> {
         let url = self.build_url("ping", None);
@@ -112,7 +259,9 @@ impl Client {
         })
     }
 
-    /// Write a point to the database
+    /// Write a point to the database.
+    ///
+    /// `precision` and `rp` override the client's defaults when given.
     pub fn write_point<'a>(
         &self,
         point: Point<'a>,
@@ -123,50 +272,63 @@ impl Client {
         self.write_points(points, precision, rp)
     }
 
-    /// Write multiple points to the database
+    /// Write multiple points to the database.
+    ///
+    /// `precision` and `rp` override the client's defaults when given.
     pub fn write_points<'a, T: IntoIterator<Item = impl Borrow<Point<'a>>>>(
         &self,
         points: T,
         precision: Option<Precision>,
         rp: Option<&str>,
     ) -> impl Future<Output = Result<(), error::Error>> {
-        let line = serialization::line_serialization(points);
+        self.write_points_with_compression(points, precision, rp, self.compression)
+    }
 
-        let mut param = vec![("db", self.db.as_str())];
+    /// Write multiple points to the database, overriding the client's compression
+    pub fn write_points_with_compression<'a, T: IntoIterator<Item = impl Borrow<Point<'a>>>>(
+        &self,
+        points: T,
+        precision: Option<Precision>,
+        rp: Option<&str>,
+        compression: Compression,
+    ) -> impl Future<Output = Result<(), error::Error>> {
+        let precision = precision.unwrap_or(self.precision);
+        let mut body = Vec::new();
+        let write = self
+            .encoder(precision)
+            .encode(points, &mut body)
+            .map(|_| self.write_line_protocol(body, precision, rp, compression));
+
+        async move { write?.await }
+    }
 
-        match precision {
-            Some(ref t) => param.push(("precision", t.to_str())),
-            None => param.push(("precision", "s")),
-        };
+    /// Write a body of line protocol, in the given precision
+    pub(crate) fn write_line_protocol(
+        &self,
+        body: Vec<u8>,
+        precision: Precision,
+        rp: Option<&str>,
+        compression: Compression,
+    ) -> impl Future<Output = Result<(), error::Error>> {
+        let mut param = vec![("db", self.db.as_str()), ("precision", precision.to_str())];
 
-        if let Some(t) = rp {
+        if let Some(t) = rp.or(self.retention_policy.as_deref()) {
             param.push(("rp", t))
         }
 
         let url = self.build_url("write", Some(param));
-        let fut = self.client.post(url).body(line).send();
-
-        async move {
-            let res = fut.await?;
-            let status = res.status().as_u16();
-            let err = res.text().await?;
-
-            match status {
-                204 => Ok(()),
-                400 => Err(error::Error::SyntaxError(serialization::conversion(&err))),
-                401 | 403 => Err(error::Error::InvalidCredentials(
-                    "Invalid authentication credentials.".to_string(),
-                )),
-                404 => Err(error::Error::DataBaseDoesNotExist(
-                    serialization::conversion(&err),
-                )),
-                500 => Err(error::Error::RetentionPolicyDoesNotExist(err)),
-                status => Err(error::Error::Unknow(format!(
-                    "Received status code {}",
-                    status
-                ))),
+        let request = serialization::compress(body, compression).map(|(body, gzipped)| {
+            let builder = self.client.post(url);
+            if gzipped {
+                builder.header(CONTENT_ENCODING, "gzip").body(body)
+            } else {
+                builder.body(body)
             }
-        }
+        });
+
+        let retry = self.retry;
+
+        async move { write_reply(retry::send(retry, request?).await?).await }
     }
 
     /// Query and return data, the data type is `Option<Vec<Node>>`
@@ -175,17 +337,173 @@ impl Client {
         q: &str,
         epoch: Option<Precision>,
     ) -> impl Future<Output = Result<Option<Vec<Node>>, error::Error>> {
-        self.query_raw(q, epoch).map_ok(|t| t.results)
+        let query = self.query_raw(q, epoch).map_ok(|t| t.results);
+        self.with_timeout(q, &[], query)
     }
 
-    /// Query and return data, the data type is `Option<Vec<Node>>`
+    /// Query with values bound to its `$name` placeholders, sent apart from the query text
+    /// so they need no quoting.
+    ///
+    /// ```Rust
+    /// use influx_db_client::QueryParam;
+    /// use std::time::{Duration, SystemTime};
+    ///
+    /// let res = client
+    ///     .query_with_params(
+    ///         "select mean(usage) from cpu where host = $host and time > now() - $window",
+    ///         &[
+    ///             ("host", QueryParam::from("server01")),
+    ///             ("window", QueryParam::from(Duration::from_secs(3600))),
+    ///         ],
+    ///         None,
+    ///     )
+    ///     .await?;
+    /// ```
+    pub fn query_with_params(
+        &self,
+        q: &str,
+        params: &[(&str, QueryParam)],
+        epoch: Option<Precision>,
+    ) -> impl Future<Output = Result<Option<Vec<Node>>, error::Error>> {
+        let query = self
+            .query_raw_with_params(q, params, epoch)
+            .map_ok(|t| t.results);
+        self.with_timeout(q, params, query)
+    }
+
+    /// Query and deserialize every row of every returned series into `T`,
+    /// see `Series::into_rows_with_epoch` for how rows are mapped.
+    ///
+    /// Fails with the error of the first statement that failed.
+    pub fn query_as<T: DeserializeOwned>(
+        &self,
+        q: &str,
+        epoch: Option<Precision>,
+    ) -> impl Future<Output = Result<Vec<T>, error::Error>> {
+        let query = self.query_raw(q, epoch).map(move |res| {
+            let mut rows = Vec::new();
+            for node in res?.into_statements() {
+                for series in node?.series.unwrap_or_default() {
+                    rows.extend(series.into_rows_with_epoch(epoch)?);
+                }
+            }
+            Ok(rows)
+        });
+        self.with_timeout(q, &[], query)
+    }
+
+    /// Query and stream the response chunk by chunk, as the server sends it.
+    ///
+    /// `chunk_size` is the maximum number of rows per chunk, 10000 by default on the server.
     pub fn query_chunked(
         &self,
         q: &str,
         epoch: Option<Precision>,
-    ) -> impl Future<Output = Result<ChunkedQuery<'static, IoRead<Cursor<Bytes>>>, error::Error>>
-    {
-        self.query_raw_chunked(q, epoch)
+        chunk_size: Option<usize>,
+    ) -> impl Future<Output = Result<ChunkedQuery, error::Error>> {
+        self.query_raw_chunked(q, epoch, chunk_size)
+    }
+
+    /// List the databases
+    pub fn list_databases(&self) -> impl Future<Output = Result<Vec<String>, error::Error>> {
+        self.query_rows::<Name>("SHOW DATABASES".to_string())
+            .map_ok(|rows| rows.into_iter().map(|(_, row)| row.name).collect())
+    }
+
+    /// List the measurements of the current database, those matching `regex` if given
+    pub fn list_measurements(
+        &self,
+        regex: Option<&str>,
+    ) -> impl Future<Output = Result<Vec<String>, error::Error>> {
+        let mut sql = "SHOW MEASUREMENTS".to_string();
+        if let Some(regex) = regex {
+            sql.push_str(" WITH MEASUREMENT =~ ");
+            sql.push_str(&serialization::quote_regex(regex));
+        }
+
+        self.query_rows::<Name>(sql)
+            .map_ok(|rows| rows.into_iter().map(|(_, row)| row.name).collect())
+    }
+
+    /// List the tag keys of each measurement, or of one measurement
+    pub fn list_tag_keys(
+        &self,
+        measurement: Option<&str>,
+    ) -> impl Future<Output = Result<BTreeMap<String, Vec<String>>, error::Error>> {
+        #[derive(Deserialize)]
+        struct Row {
+            #[serde(rename = "tagKey")]
+            tag_key: String,
+        }
+
+        let sql = format!("SHOW TAG KEYS{}", from_clause(measurement));
+        self.query_rows::<Row>(sql)
+            .map_ok(|rows| group_by_series(rows, |row| row.tag_key))
+    }
+
+    /// List the values of a tag key in each measurement, or in one measurement
+    pub fn list_tag_values(
+        &self,
+        key: &str,
+        measurement: Option<&str>,
+    ) -> impl Future<Output = Result<BTreeMap<String, Vec<String>>, error::Error>> {
+        #[derive(Deserialize)]
+        struct Row {
+            value: String,
+        }
+
+        let sql = format!(
+            "SHOW TAG VALUES{} WITH KEY = {}",
+            from_clause(measurement),
+            serialization::quote_ident(key)
+        );
+        self.query_rows::<Row>(sql)
+            .map_ok(|rows| group_by_series(rows, |row| row.value))
+    }
+
+    /// List the field keys and their types of each measurement, or of one measurement
+    pub fn list_field_keys(
+        &self,
+        measurement: Option<&str>,
+    ) -> impl Future<Output = Result<BTreeMap<String, Vec<FieldKey>>, error::Error>> {
+        let sql = format!("SHOW FIELD KEYS{}", from_clause(measurement));
+        self.query_rows::<FieldKey>(sql)
+            .map_ok(|rows| group_by_series(rows, |row| row))
+    }
+
+    /// List the series keys, such as `cpu,host=server01`, of the current database
+    /// or of one measurement
+    pub fn list_series(
+        &self,
+        measurement: Option<&str>,
+    ) -> impl Future<Output = Result<Vec<String>, error::Error>> {
+        #[derive(Deserialize)]
+        struct Row {
+            key: String,
+        }
+
+        let sql = format!("SHOW SERIES{}", from_clause(measurement));
+        self.query_rows::<Row>(sql)
+            .map_ok(|rows| rows.into_iter().map(|(_, row)| row.key).collect())
+    }
+
+    /// Estimate the number of series of the current database, or of one measurement
+    ///
+    /// Without a measurement the server answers with a sketch based estimate,
+    /// which may differ slightly from the exact number of series.
+    pub fn show_series_cardinality(
+        &self,
+        measurement: Option<&str>,
+    ) -> impl Future<Output = Result<u64, error::Error>> {
+        #[derive(Deserialize)]
+        struct Row {
+            #[serde(alias = "cardinality estimation")]
+            count: u64,
+        }
+
+        let sql = format!("SHOW SERIES CARDINALITY{}", from_clause(measurement));
+        self.query_rows::<Row>(sql)
+            .map_ok(|rows| rows.into_iter().map(|(_, row)| row.count).sum())
     }
 
     /// Drop measurement
@@ -201,6 +519,51 @@ impl Client {
         self.query_raw(&sql, None).map_ok(|_| ())
     }
 
+    /// Delete the points of a measurement, or of all measurements, that match a condition
+    /// on tags and time.
+    ///
+    /// At least one of `measurement` and `condition` must be given.
+    ///
+    /// ```Rust
+    /// use influx_db_client::influxql::{Condition, Time};
+    /// use std::time::Duration;
+    ///
+    /// let old_on_server01 = Condition::tag_eq("host", "server01")
+    ///     .and(Condition::time_lt(Time::Ago(Duration::from_secs(86400))));
+    /// client.delete_series(Some("cpu"), Some(old_on_server01)).await?;
+    /// ```
+    pub fn delete_series(
+        &self,
+        measurement: Option<&str>,
+        condition: Option<Condition>,
+    ) -> impl Future<Output = Result<(), error::Error>> {
+        let query =
+            series_statement("Delete", measurement, condition).map(|sql| self.execute(&sql));
+
+        async move { query?.await }
+    }
+
+    /// Drop the series of a measurement, or of all measurements, that match a condition
+    /// on tags, with all their points and their index entries.
+    ///
+    /// At least one of `measurement` and `condition` must be given, and the condition
+    /// can't restrict time.
+    pub fn drop_series(
+        &self,
+        measurement: Option<&str>,
+        condition: Option<Condition>,
+    ) -> impl Future<Output = Result<(), error::Error>> {
+        let query = match condition {
+            Some(ref condition) if condition.has_time() => Err(error::Error::InvalidArgument(
+                "Drop series doesn't support time predicates, use delete_series".to_string(),
+            )),
+            _ => series_statement("Drop series", measurement, condition),
+        }
+        .map(|sql| self.execute(&sql));
+
+        async move { query?.await }
+    }
+
     /// Create a new database in InfluxDB.
     pub fn create_database(&self, dbname: &str) -> impl Future<Output = Result<(), error::Error>> {
         let sql = format!("Create database {}", serialization::quote_ident(dbname));
@@ -238,14 +601,14 @@ impl Client {
             }
         };
 
-        self.query_raw(&sql, None).map_ok(|_| ())
+        self.execute(&sql)
     }
 
     /// Drop a user from InfluxDB.
     pub fn drop_user(&self, user: &str) -> impl Future<Output = Result<(), error::Error>> {
         let sql = format!("Drop user {}", serialization::quote_ident(user));
 
-        self.query_raw(&sql, None).map_ok(|_| ())
+        self.execute(&sql)
     }
 
     /// Change the password of an existing user.
@@ -260,7 +623,7 @@ impl Client {
             serialization::quote_literal(passwd)
         );
 
-        self.query_raw(&sql, None).map_ok(|_| ())
+        self.execute(&sql)
     }
 
     /// Grant cluster administration privileges to a user.
@@ -273,7 +636,7 @@ impl Client {
             serialization::quote_ident(user)
         );
 
-        self.query_raw(&sql, None).map_ok(|_| ())
+        self.execute(&sql)
     }
 
     /// Revoke cluster administration privileges from a user.
@@ -286,17 +649,15 @@ impl Client {
             serialization::quote_ident(user)
         );
 
-        self.query_raw(&sql, None).map_ok(|_| ())
+        self.execute(&sql)
     }
 
-    /// Grant a privilege on a database to a user.
-    /// :param privilege: the privilege to grant, one of 'read', 'write'
-    /// or 'all'. The string is case-insensitive
+    /// Grant a privilege on a database to a user, replacing the one it had
     pub fn grant_privilege(
         &self,
         user: &str,
         db: &str,
-        privilege: &str,
+        privilege: Privilege,
     ) -> impl Future<Output = Result<(), error::Error>> {
         let sql = format!(
             "Grant {} on {} to {}",
@@ -305,17 +666,15 @@ impl Client {
             serialization::quote_ident(user)
         );
 
-        self.query_raw(&sql, None).map_ok(|_| ())
+        self.execute(&sql)
     }
 
     /// Revoke a privilege on a database from a user.
-    /// :param privilege: the privilege to grant, one of 'read', 'write'
-    /// or 'all'. The string is case-insensitive
     pub fn revoke_privilege(
         &self,
         user: &str,
         db: &str,
-        privilege: &str,
+        privilege: Privilege,
     ) -> impl Future<Output = Result<(), error::Error>> {
         let sql = format!(
             "Revoke {0} on {1} from {2}",
@@ -324,21 +683,163 @@ impl Client {
             serialization::quote_ident(user)
         );
 
-        self.query_raw(&sql, None).map_ok(|_| ())
+        self.execute(&sql)
+    }
+
+    /// List the users
+    pub fn list_users(&self) -> impl Future<Output = Result<Vec<UserInfo>, error::Error>> {
+        self.query_rows::<UserInfo>("SHOW USERS".to_string())
+            .map_ok(|rows| rows.into_iter().map(|(_, user)| user).collect())
+    }
+
+    /// The privilege of a user on each database it has one on
+    pub fn show_grants(
+        &self,
+        user: &str,
+    ) -> impl Future<Output = Result<BTreeMap<String, Privilege>, error::Error>> {
+        #[derive(Deserialize)]
+        struct Grant {
+            database: String,
+            privilege: String,
+        }
+
+        let sql = format!("SHOW GRANTS FOR {}", serialization::quote_ident(user));
+        self.query_rows::<Grant>(sql).map(|rows| {
+            let mut grants = BTreeMap::new();
+            for (_, grant) in rows? {
+                if grant.privilege != "NO PRIVILEGES" {
+                    grants.insert(grant.database, grant.privilege.parse()?);
+                }
+            }
+            Ok(grants)
+        })
+    }
+
+    /// Grant and revoke privileges so that the user has exactly the `desired` ones
+    ///
+    /// ```Rust
+    /// use influx_db_client::schema::Privilege;
+    /// use std::collections::BTreeMap;
+    ///
+    /// let mut desired = BTreeMap::new();
+    /// desired.insert("metrics".to_string(), Privilege::Read);
+    /// client.sync_user_grants("grafana", &desired).await?;
+    /// ```
+    pub async fn sync_user_grants(
+        &self,
+        user: &str,
+        desired: &BTreeMap<String, Privilege>,
+    ) -> Result<(), error::Error> {
+        let current = self.show_grants(user).await?;
+
+        for (db, privilege) in &current {
+            if !desired.contains_key(db) {
+                self.revoke_privilege(user, db, *privilege).await?;
+            }
+        }
+        for (db, privilege) in desired {
+            if current.get(db) != Some(privilege) {
+                self.grant_privilege(user, db, *privilege).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// List the queries running on the server
+    pub fn show_queries(&self) -> impl Future<Output = Result<Vec<RunningQuery>, error::Error>> {
+        self.query_rows::<RunningQuery>("SHOW QUERIES".to_string())
+            .map_ok(|rows| rows.into_iter().map(|(_, query)| query).collect())
+    }
+
+    /// Stop a running query, `qid` is its id in `show_queries`
+    pub fn kill_query(&self, qid: u64) -> impl Future<Output = Result<(), error::Error>> {
+        let sql = format!("Kill query {}", qid);
+
+        self.execute(&sql)
+    }
+
+    /// Kill the running query of the current database with the same `tokens`
+    /// that started less than `elapsed` ago, unless several match
+    async fn kill_running(
+        &self,
+        tokens: &[QueryToken],
+        elapsed: Duration,
+    ) -> Result<(), error::Error> {
+        let matching: Vec<_> = self
+            .show_queries()
+            .await?
+            .into_iter()
+            .filter(|running| {
+                running.database == self.db
+                    && running.duration <= elapsed
+                    && query_tokens(&running.query, &[]) == tokens
+            })
+            .collect();
+
+        match matching[..] {
+            [ref running] => self.kill_query(running.qid).await,
+            _ => Ok(()),
+        }
+    }
+
+    /// Apply the query timeout to the future of `q` bound to `params`,
+    /// see `set_query_timeout`
+    fn with_timeout<T>(
+        &self,
+        q: &str,
+        params: &[(&str, QueryParam)],
+        query: impl Future<Output = Result<T, error::Error>>,
+    ) -> impl Future<Output = Result<T, error::Error>> {
+        let timeout = self
+            .query_timeout
+            .map(|timeout| (timeout, self.clone(), query_tokens(q, params)));
+
+        async move {
+            let (timeout, client, tokens) = match timeout {
+                None => return query.await,
+                Some(timeout) => timeout,
+            };
+
+            // The request is sent when `query` is first polled, right below
+            let guard = KillOnDrop::new(client, tokens);
+            match tokio::time::timeout(timeout, query).await {
+                Ok(res) => {
+                    guard.disarm();
+                    res
+                }
+                Err(_) => Err(error::Error::Timeout(format!(
+                    "Query timed out after {:?}",
+                    timeout
+                ))),
+            }
+        }
+    }
+
+    /// List the retention policies of a database, the current one if `db` is `None`
+    pub fn list_retention_policies(
+        &self,
+        db: Option<&str>,
+    ) -> impl Future<Output = Result<Vec<RetentionPolicy>, error::Error>> {
+        let sql = format!(
+            "Show retention policies on {}",
+            serialization::quote_ident(db.unwrap_or(&self.db))
+        );
+
+        self.query_rows::<RetentionPolicy>(sql)
+            .map_ok(|rows| rows.into_iter().map(|(_, rp)| rp).collect())
     }
 
     /// Create a retention policy for a database.
-    /// :param duration: the duration of the new retention policy.
-    ///  Durations such as 1h, 90m, 12h, 7d, and 4w, are all supported
-    ///  and mean 1 hour, 90 minutes, 12 hours, 7 day, and 4 weeks,
-    ///  respectively. For infinite retention – meaning the data will
-    ///  never be deleted – use 'INF' for duration.
-    ///  The minimum retention period is 1 hour.
+    /// :param duration: how long data is kept, `InfluxDuration::Infinite`
+    ///  to never delete it. The minimum retention period is 1 hour.
+    /// :param shard_duration: time range of each shard group, chosen by
+    ///  the server from the duration if `None`
     pub fn create_retention_policy(
         &self,
         name: &str,
-        duration: &str,
-        replication: &str,
+        duration: InfluxDuration,
+        replication: u32,
+        shard_duration: Option<InfluxDuration>,
         default: bool,
         db: Option<&str>,
     ) -> impl Future<Output = Result<(), error::Error>> {
@@ -350,27 +851,65 @@ impl Client {
             }
         };
 
-        let sql: String = {
-            if default {
-                format!(
-                    "Create retention policy {} on {} duration {} replication {} default",
-                    serialization::quote_ident(name),
-                    serialization::quote_ident(database),
-                    duration,
-                    replication
-                )
-            } else {
-                format!(
-                    "Create retention policy {} on {} duration {} replication {}",
-                    serialization::quote_ident(name),
-                    serialization::quote_ident(database),
-                    duration,
-                    replication
-                )
-            }
+        let mut sql = format!(
+            "Create retention policy {} on {} duration {} replication {}",
+            serialization::quote_ident(name),
+            serialization::quote_ident(database),
+            duration,
+            replication
+        );
+        if let Some(shard_duration) = shard_duration {
+            sql.push_str(&format!(" shard duration {}", shard_duration));
+        }
+        if default {
+            sql.push_str(" default");
+        }
+
+        self.execute(&sql)
+    }
+
+    /// Change an existing retention policy of a database, leaving the settings
+    /// that are `None` as they are.
+    ///
+    /// A policy can be made the default one, but not undone as default:
+    /// make another one the default instead.
+    pub fn alter_retention_policy(
+        &self,
+        name: &str,
+        duration: Option<InfluxDuration>,
+        replication: Option<u32>,
+        shard_duration: Option<InfluxDuration>,
+        default: bool,
+        db: Option<&str>,
+    ) -> impl Future<Output = Result<(), error::Error>> {
+        let mut sql = format!(
+            "Alter retention policy {} on {}",
+            serialization::quote_ident(name),
+            serialization::quote_ident(db.unwrap_or(&self.db))
+        );
+        let clauses = sql.len();
+        if let Some(duration) = duration {
+            sql.push_str(&format!(" duration {}", duration));
+        }
+        if let Some(replication) = replication {
+            sql.push_str(&format!(" replication {}", replication));
+        }
+        if let Some(shard_duration) = shard_duration {
+            sql.push_str(&format!(" shard duration {}", shard_duration));
+        }
+        if default {
+            sql.push_str(" default");
+        }
+
+        let query = if sql.len() == clauses {
+            Err(error::Error::InvalidArgument(
+                "Nothing to alter in the retention policy".to_string(),
+            ))
+        } else {
+            Ok(self.execute(&sql))
         };
 
-        self.query_raw(&sql, None).map_ok(|_| ())
+        async move { query?.await }
     }
 
     /// Drop an existing retention policy for a database.
@@ -393,14 +932,85 @@ impl Client {
             serialization::quote_ident(database)
         );
 
-        self.query_raw(&sql, None).map_ok(|_| ())
+        self.execute(&sql)
+    }
+
+    /// Create a continuous query on a database, the current one if `db` is `None`.
+    ///
+    /// `query` is the `SELECT ... INTO ... GROUP BY time(...)` statement, as a string or a
+    /// `influxql::SelectQuery`. `resample_every` and `resample_for` set how often it runs
+    /// and how far back it computes, by default every `GROUP BY time()` interval over the
+    /// last interval.
+    ///
+    /// ```Rust
+    /// use influx_db_client::influxql::{Aggregate, Field, Measurement, SelectQuery};
+    /// use std::time::Duration;
+    ///
+    /// let downsample = SelectQuery::new("cpu")
+    ///     .add_field(Field::aggregate(Aggregate::Mean, "usage").set_alias("usage"))
+    ///     .set_into(Measurement::new("cpu_1h").set_retention_policy("long"))
+    ///     .set_group_by_time(Duration::from_secs(3600), None)
+    ///     .add_group_by_tag("*");
+    ///
+    /// client
+    ///     .create_continuous_query("cpu_1h", None, &downsample, None, Some(Duration::from_secs(7200)))
+    ///     .await?;
+    /// ```
+    pub fn create_continuous_query<Q: fmt::Display + ?Sized>(
+        &self,
+        name: &str,
+        db: Option<&str>,
+        query: &Q,
+        resample_every: Option<Duration>,
+        resample_for: Option<Duration>,
+    ) -> impl Future<Output = Result<(), error::Error>> {
+        let mut sql = format!(
+            "Create continuous query {} on {}",
+            serialization::quote_ident(name),
+            serialization::quote_ident(db.unwrap_or(&self.db))
+        );
+        if resample_every.is_some() || resample_for.is_some() {
+            sql.push_str(" resample");
+        }
+        if let Some(every) = resample_every {
+            sql.push_str(&format!(" every {}", InfluxDuration::Finite(every)));
+        }
+        if let Some(range) = resample_for {
+            sql.push_str(&format!(" for {}", InfluxDuration::Finite(range)));
+        }
+        sql.push_str(&format!(" begin {} end", query));
+
+        self.execute(&sql)
+    }
+
+    /// Drop a continuous query of a database, the current one if `db` is `None`
+    pub fn drop_continuous_query(
+        &self,
+        name: &str,
+        db: Option<&str>,
+    ) -> impl Future<Output = Result<(), error::Error>> {
+        let sql = format!(
+            "Drop continuous query {} on {}",
+            serialization::quote_ident(name),
+            serialization::quote_ident(db.unwrap_or(&self.db))
+        );
+
+        self.execute(&sql)
+    }
+
+    /// List the continuous queries of every database
+    pub fn list_continuous_queries(
+        &self,
+    ) -> impl Future<Output = Result<BTreeMap<String, Vec<ContinuousQuery>>, error::Error>> {
+        self.query_rows::<ContinuousQuery>("Show continuous queries".to_string())
+            .map_ok(|rows| group_by_series(rows, |cq| cq))
     }
 
     fn send_request(
         &self,
         q: &str,
         epoch: Option<Precision>,
-        chunked: bool,
+        extra: Vec<(&str, &str)>,
     ) -> impl Future<Output = Result<Response, error::Error>> {
         let mut param = vec![("db", self.db.as_str()), ("q", q)];
 
@@ -408,9 +1018,7 @@ impl Client {
             param.push(("epoch", t.to_str()))
         }
 
-        if chunked {
-            param.push(("chunked", "true"));
-        }
+        param.extend(extra);
 
         let url = self.build_url("query", Some(param));
 
@@ -427,51 +1035,95 @@ impl Client {
             builder = builder.bearer_auth(token);
         }
 
-        let resp_future = builder.send().boxed();
+        let resp_future = retry::send(self.retry, builder);
 
         async move {
-            let res = resp_future.await?;
-            match res.status().as_u16() {
-                200 => Ok(res),
-                400 => {
-                    let json_data: Query = res.json().await?;
-
-                    Err(error::Error::SyntaxError(serialization::conversion(
-                        &json_data.error.unwrap(),
-                    )))
-                }
-                401 | 403 => Err(error::Error::InvalidCredentials(
-                    "Invalid authentication credentials.".to_string(),
+            match resp_future.await? {
+                Reply::Success(res) if res.status().as_u16() == 200 => Ok(res),
+                Reply::Success(res) => Err(error::Error::from_response(
+                    res.status().as_u16(),
+                    &res.text().await?,
+                    RequestKind::Query,
+                )),
+                Reply::Failure(status, body) => Err(error::Error::from_response(
+                    status,
+                    &body,
+                    RequestKind::Query,
                 )),
-                _ => Err(error::Error::Unknow("There is something wrong".to_string())),
             }
         }
     }
 
+    /// Run statements that return no data, failing with the first statement error
+    fn execute(&self, sql: &str) -> impl Future<Output = Result<(), error::Error>> {
+        self.query_raw(sql, None).map(|res| {
+            res?.into_statements()
+                .into_iter()
+                .try_for_each(|node| node.map(|_| ()))
+        })
+    }
+
+    /// Query and return the rows of every series of the first statement,
+    /// with the name of their series
+    fn query_rows<T: DeserializeOwned>(
+        &self,
+        sql: String,
+    ) -> impl Future<Output = Result<Vec<(Option<String>, T)>, error::Error>> {
+        self.query_raw(&sql, None).map(|res| {
+            let mut rows = Vec::new();
+            if let Some(node) = res?.into_statements().into_iter().next() {
+                for series in node?.series.unwrap_or_default() {
+                    let name = series.name.clone();
+                    rows.extend(
+                        series
+                            .into_rows()?
+                            .into_iter()
+                            .map(|row| (name.clone(), row)),
+                    );
+                }
+            }
+            Ok(rows)
+        })
+    }
+
     /// Query and return to the native json structure
     fn query_raw(
         &self,
         q: &str,
         epoch: Option<Precision>,
     ) -> impl Future<Output = Result<Query, error::Error>> {
-        let resp_future = self.send_request(q, epoch, false);
+        let resp_future = self.send_request(q, epoch, Vec::new());
         async move { Ok(resp_future.await?.json().await?) }
     }
 
+    /// Query with bound parameters and return to the native json structure
+    fn query_raw_with_params(
+        &self,
+        q: &str,
+        params: &[(&str, QueryParam)],
+        epoch: Option<Precision>,
+    ) -> impl Future<Output = Result<Query, error::Error>> {
+        let params: BTreeMap<_, _> = params.iter().map(|(k, v)| (*k, v)).collect();
+        let resp_future = serde_json::to_string(&params)
+            .map(|params| self.send_request(q, epoch, vec![("params", &params)]));
+        async move { Ok(resp_future?.await?.json().await?) }
+    }
+
     /// Query and return to the native json structure
     fn query_raw_chunked(
         &self,
         q: &str,
         epoch: Option<Precision>,
-    ) -> impl Future<Output = Result<ChunkedQuery<'static, IoRead<Cursor<Bytes>>>, error::Error>>
-    {
-        let resp_future = self.send_request(q, epoch, true);
-        async move {
-            let response = resp_future.await?;
-            let bytes = Cursor::new(response.bytes().await?);
-            let stream = serde_json::Deserializer::from_reader(bytes).into_iter::<Query>();
-            Ok(stream)
+        chunk_size: Option<usize>,
+    ) -> impl Future<Output = Result<ChunkedQuery, error::Error>> {
+        let chunk_size = chunk_size.map(|n| n.to_string());
+        let mut extra = vec![("chunked", "true")];
+        if let Some(ref n) = chunk_size {
+            extra.push(("chunk_size", n));
         }
+
+        let resp_future = self.send_request(q, epoch, extra);
+        async move { Ok(ChunkedQuery::from_response(resp_future.await?)) }
     }
 
     /// Constructs the full URL for an API call.
@@ -495,6 +1147,197 @@ impl Client {
     }
 }
 
+/// The result of a write, which succeeded if the server replied `204 No Content`
+async fn write_reply(reply: Reply) -> Result<(), error::Error> {
+    match reply {
+        Reply::Success(res) if res.status().as_u16() == 204 => Ok(()),
+        Reply::Success(res) => Err(error::Error::from_response(
+            res.status().as_u16(),
+            &res.text().await?,
+            RequestKind::Write,
+        )),
+        Reply::Failure(status, body) => Err(error::Error::from_response(
+            status,
+            &body,
+            RequestKind::Write,
+        )),
+    }
+}
+
+/// Row of `SHOW DATABASES` and `SHOW MEASUREMENTS`
+#[derive(Deserialize)]
+struct Name {
+    name: String,
+}
+
+/// ` FROM "measurement"`, or nothing
+fn from_clause(measurement: Option<&str>) -> String {
+    measurement
+        .map(|m| format!(" FROM {}", serialization::quote_ident(m)))
+        .unwrap_or_default()
+}
+
+/// Kills the server side of a query when dropped before being disarmed
+struct KillOnDrop {
+    client: Client,
+    query: Option<Vec<QueryToken>>,
+    started: Instant,
+}
+
+impl KillOnDrop {
+    fn new(client: Client, tokens: Vec<QueryToken>) -> Self {
+        KillOnDrop {
+            client,
+            query: Some(tokens),
+            started: Instant::now(),
+        }
+    }
+
+    /// The query completed, there is nothing to kill
+    fn disarm(mut self) {
+        self.query = None;
+    }
+}
+
+impl Drop for KillOnDrop {
+    fn drop(&mut self) {
+        if let (Some(query), Ok(runtime)) =
+            (self.query.take(), tokio::runtime::Handle::try_current())
+        {
+            let client = self.client.clone();
+            let elapsed = self.started.elapsed();
+            runtime.spawn(async move {
+                // Best effort, the query may have completed meanwhile
+                let _ = client.kill_running(&query, elapsed).await;
+            });
+        }
+    }
+}
+
+/// A token of a query, as comparable with the text `SHOW QUERIES` reports
+#[derive(Debug, PartialEq)]
+enum QueryToken {
+    /// keyword or identifier, lowercase and unquoted
+    Word(String),
+    /// string literal, unescaped
+    Str(String),
+    /// integer literal
+    Integer(String),
+    /// float literal, rounded to the 3 decimals the server prints
+    Float(String),
+    /// duration literal, `60m` and `1h` are the same
+    Duration(Duration),
+    /// any other character
+    Symbol(char),
+}
+
+/// The tokens of `q` with `params` bound in place of their `$name`
+fn query_tokens(q: &str, params: &[(&str, QueryParam)]) -> Vec<QueryToken> {
+    let is_word = |c: &char| c.is_alphanumeric() || *c == '_';
+    let mut tokens = Vec::new();
+    let mut chars = q.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        // numbers take their decimal point and duration units along
+        let mut word = |first: char, number: bool| {
+            let mut word = first.to_string();
+            while let Some(c) = chars.next_if(|c| is_word(c) || number && *c == '.') {
+                word.push(c);
+            }
+            word
+        };
+
+        match c {
+            ';' => {}
+            c if c.is_whitespace() => {}
+            '\'' | '"' => {
+                let mut text = String::new();
+                while let Some(next) = chars.next() {
+                    match next {
+                        '\\' => text.extend(chars.next()),
+                        next if next == c => break,
+                        next => text.push(next),
+                    }
+                }
+                tokens.push(if c == '"' {
+                    QueryToken::Word(text.to_lowercase())
+                } else {
+                    QueryToken::Str(text)
+                });
+            }
+            '$' => {
+                let name = word(c, false);
+                match params.iter().find(|(key, _)| *key == &name[1..]) {
+                    Some((_, param)) => tokens.extend(query_tokens(&param_literal(param), &[])),
+                    None => tokens.push(QueryToken::Word(name.to_lowercase())),
+                }
+            }
+            c if c.is_ascii_digit() => {
+                let literal = word(c, true);
+                tokens.push(if literal.bytes().all(|b| b.is_ascii_digit()) {
+                    QueryToken::Integer(literal)
+                } else if let Ok(f) = literal.parse::<f64>() {
+                    QueryToken::Float(format!("{:.3}", f))
+                } else if let Ok(InfluxDuration::Finite(d)) = literal.parse() {
+                    QueryToken::Duration(d)
+                } else {
+                    QueryToken::Word(literal.to_lowercase())
+                });
+            }
+            c if is_word(&c) => tokens.push(QueryToken::Word(word(c, false).to_lowercase())),
+            c => tokens.push(QueryToken::Symbol(c)),
+        }
+    }
+    tokens
+}
+
+/// The literal the server substitutes for a bound parameter
+fn param_literal(param: &QueryParam) -> String {
+    match *param {
+        QueryParam::String(ref s) => serialization::quote_literal(s),
+        QueryParam::Integer(i) => i.to_string(),
+        QueryParam::Float(f) => format!("{:.3}", f),
+        QueryParam::Boolean(b) => b.to_string(),
+        QueryParam::Duration(d) => serialization::format_duration(d),
+        QueryParam::Time(t) => t.to_precision(Precision::Nanoseconds).to_string(),
+    }
+}
+
+/// `DELETE` or `DROP SERIES` with its optional `FROM` and `WHERE` clauses
+fn series_statement(
+    statement: &str,
+    measurement: Option<&str>,
+    condition: Option<Condition>,
+) -> Result<String, error::Error> {
+    if measurement.is_none() && condition.is_none() {
+        return Err(error::Error::InvalidArgument(format!(
+            "{} needs a measurement or a condition",
+            statement
+        )));
+    }
+
+    let mut sql = format!("{}{}", statement, from_clause(measurement));
+    if let Some(condition) = condition {
+        sql.push_str(&format!(" where {}", condition));
+    }
+    Ok(sql)
+}
+
+/// Group rows by the name of their series, the measurement or the database
+fn group_by_series<T, U>(
+    rows: Vec<(Option<String>, T)>,
+    f: impl Fn(T) -> U,
+) -> BTreeMap<String, Vec<U>> {
+    let mut groups = BTreeMap::new();
+    for (measurement, row) in rows {
+        groups
+            .entry(measurement.unwrap_or_default())
+            .or_insert_with(Vec::new)
+            .push(f(row));
+    }
+    groups
+}
+
 impl Default for Client {
     /// connecting for default database `test` and host `http://localhost:8086`
     fn default() -> Self {
@@ -502,9 +1345,200 @@ impl Default for Client {
     }
 }
 
+/// The client to the write API of InfluxDB 2.x, writing to a bucket of an organization
+///
+/// ```Rust
+/// use influx_db_client::{Point, Precision, V2Client};
+///
+/// let client = V2Client::new("http://localhost:8086".parse()?, "my-org", "my-bucket")
+///     .set_token("my-token");
+/// let point = Point::new("cpu").add_tag("host", "a").add_field("usage", 0.5);
+/// client.write_point(point, Some(Precision::Milliseconds)).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct V2Client {
+    host: Url,
+    org: String,
+    bucket: String,
+    token: Option<String>,
+    client: HttpClient,
+    encoder: LineProtocolEncoder,
+    compression: Compression,
+    precision: Precision,
+    retry: RetryPolicy,
+}
+
+impl V2Client {
+    /// Create a new InfluxDB 2.x client
+    pub fn new<T>(host: Url, org: T, bucket: T) -> Self
+    where
+        T: Into<String>,
+    {
+        V2Client::new_with_client(host, org, bucket, HttpClient::default())
+    }
+
+    /// Create a new InfluxDB 2.x client with custom reqwest's client.
+    pub fn new_with_client<T>(host: Url, org: T, bucket: T, client: HttpClient) -> Self
+    where
+        T: Into<String>,
+    {
+        V2Client {
+            host,
+            org: org.into(),
+            bucket: bucket.into(),
+            token: None,
+            client,
+            encoder: LineProtocolEncoder::new(),
+            compression: Compression::None,
+            precision: Precision::Seconds,
+            retry: RetryPolicy::never(),
+        }
+    }
+
+    /// Change the client's bucket
+    pub fn switch_bucket<T>(&mut self, bucket: T)
+    where
+        T: Into<String>,
+    {
+        self.bucket = bucket.into();
+    }
+
+    /// Set the API token sent as `Authorization: Token <token>`
+    pub fn set_token<T>(mut self, token: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Validate every point before writing, see `Point::validate`.
+    pub fn set_strict_validation(mut self, strict: bool) -> Self {
+        self.encoder = self.encoder.set_validation(strict);
+        self
+    }
+
+    /// Write fields sorted by key as well as tags, giving canonical lines
+    pub fn set_sort_fields(mut self, sort_fields: bool) -> Self {
+        self.encoder = self.encoder.set_sort_fields(sort_fields);
+        self
+    }
+
+    /// Set the compression of write bodies, `Compression::None` by default
+    pub fn set_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Set the precision used by writes that don't pass one, `Precision::Seconds` by default.
+    ///
+    /// InfluxDB 2.x doesn't accept `Precision::Minutes` and `Precision::Hours`.
+    pub fn set_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Set the retrying of writes, `RetryPolicy::never()` by default
+    pub fn set_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// View the organization
+    pub fn get_org(&self) -> &str {
+        self.org.as_str()
+    }
+
+    /// View the current bucket
+    pub fn get_bucket(&self) -> &str {
+        self.bucket.as_str()
+    }
+
+    /// View the default write precision
+    pub fn get_precision(&self) -> Precision {
+        self.precision
+    }
+
+    /// Write a point to the bucket.
+    ///
+    /// `precision` overrides the client's default when given.
+    pub fn write_point<'a>(
+        &self,
+        point: Point<'a>,
+        precision: Option<Precision>,
+    ) -> impl Future<Output = Result<(), error::Error>> + 'a {
+        let points = Points::new(point);
+        self.write_points(points, precision)
+    }
+
+    /// Write multiple points to the bucket.
+    ///
+    /// `precision` overrides the client's default when given.
+    pub fn write_points<'a, T: IntoIterator<Item = impl Borrow<Point<'a>>>>(
+        &self,
+        points: T,
+        precision: Option<Precision>,
+    ) -> impl Future<Output = Result<(), error::Error>> {
+        let precision = precision.unwrap_or(self.precision);
+        let mut body = Vec::new();
+        let write = self
+            .encoder
+            .clone()
+            .set_precision(precision)
+            .encode(points, &mut body)
+            .and_then(|_| self.write_line_protocol(body, precision));
+
+        async move { write?.await }
+    }
+
+    /// Write a body of line protocol, in the given precision
+    fn write_line_protocol(
+        &self,
+        body: Vec<u8>,
+        precision: Precision,
+    ) -> Result<impl Future<Output = Result<(), error::Error>>, error::Error> {
+        let precision = match precision {
+            Precision::Nanoseconds => "ns",
+            Precision::Microseconds => "us",
+            Precision::Milliseconds => "ms",
+            Precision::Seconds => "s",
+            Precision::Minutes | Precision::Hours => {
+                return Err(error::Error::InvalidArgument(format!(
+                    "InfluxDB 2.x doesn't accept the precision '{}'",
+                    precision.to_str()
+                )))
+            }
+        };
+
+        let url = Url::parse_with_params(
+            self.host.join("api/v2/write").unwrap().as_str(),
+            &[
+                ("org", self.org.as_str()),
+                ("bucket", self.bucket.as_str()),
+                ("precision", precision),
+            ],
+        )
+        .unwrap();
+
+        let (body, gzipped) = serialization::compress(body, self.compression)?;
+        let mut builder = self.client.post(url).body(body);
+        if gzipped {
+            builder = builder.header(CONTENT_ENCODING, "gzip");
+        }
+        if let Some(ref token) = self.token {
+            builder = builder.header(AUTHORIZATION, format!("Token {}", token));
+        }
+
+        let retry = self.retry;
+
+        Ok(async move { write_reply(retry::send(retry, builder).await?).await })
+    }
+}
+
 /// Udp client
 pub struct UdpClient {
     hosts: Vec<SocketAddr>,
+    encoder: LineProtocolEncoder,
 }
 
 impl UdpClient {
@@ -512,6 +1546,7 @@ impl UdpClient {
     pub fn new(address: SocketAddr) -> Self {
         UdpClient {
             hosts: vec![address],
+            encoder: LineProtocolEncoder::new(),
         }
     }
 
@@ -519,10 +1554,34 @@ impl UdpClient {
     pub fn with_host<TSA: ToSocketAddrs>(tsa: TSA) -> Result<Self, error::Error> {
         let result = Self {
             hosts: tsa.to_socket_addrs()?.collect(),
+            encoder: LineProtocolEncoder::new(),
         };
         Ok(result)
     }
 
+    /// Set the precision absolute timestamps are converted to, nanoseconds by default.
+    ///
+    /// It must match the `precision` of the server's udp listener.
+    pub fn set_precision(mut self, precision: Precision) -> Self {
+        self.encoder = self.encoder.set_precision(precision);
+        self
+    }
+
+    /// Validate every point before sending, see `Point::validate`.
+    ///
+    /// UDP writes get no response from the server, so this is the only way
+    /// to learn about invalid points.
+    pub fn set_strict_validation(mut self, strict: bool) -> Self {
+        self.encoder = self.encoder.set_validation(strict);
+        self
+    }
+
+    /// Write fields sorted by key as well as tags, giving canonical lines
+    pub fn set_sort_fields(mut self, sort_fields: bool) -> Self {
+        self.encoder = self.encoder.set_sort_fields(sort_fields);
+        self
+    }
+
     /// add udp host.
     pub fn add_host(&mut self, address: SocketAddr) {
         self.hosts.push(address)
@@ -535,11 +1594,11 @@ impl UdpClient {
 
     /// Send Points to influxdb.
     pub fn write_points(&self, points: Points) -> Result<(), error::Error> {
+        let mut line = Vec::new();
+        self.encoder.encode(points, &mut line)?;
         let socket = UdpSocket::bind("0.0.0.0:0")?;
 
-        let line = serialization::line_serialization(points);
-        let line = line.as_bytes();
-        socket.send_to(line, self.hosts.as_slice())?;
+        socket.send_to(&line, self.hosts.as_slice())?;
 
         Ok(())
     }
@@ -560,6 +1619,9 @@ impl FromIterator<SocketAddr> for UdpClient {
             hosts.push(i);
         }
 
-        UdpClient { hosts }
+        UdpClient {
+            hosts,
+            encoder: LineProtocolEncoder::new(),
+        }
     }
 }
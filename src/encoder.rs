@@ -0,0 +1,438 @@
+use crate::{error::Error, Point, Precision, Value};
+use bytes::{BufMut, BytesMut};
+use std::{borrow::Borrow, collections::HashMap, io};
+
+/// Encodes points to line protocol directly into a writer.
+///
+/// Tags are always written sorted by key, as InfluxDB recommends, so the same point
+/// always encodes to the same line. Fields keep the map order unless `set_sort_fields` is set.
+///
+/// Escaping only copies when a byte needs an escape and numbers are formatted in place;
+/// the only allocation is the list used to sort a point with several tags. The encoder writes
+/// many small slices, so wrap unbuffered writers such as sockets in a `BufWriter`.
+///
+/// ```
+/// use influx_db_client::{LineProtocolEncoder, Point};
+///
+/// let point = Point::new("cpu").add_tag("host", "a").add_field("usage", 0.5);
+/// let mut buf = Vec::new();
+/// LineProtocolEncoder::new().encode(&[point], &mut buf).unwrap();
+///
+/// assert_eq!(buf, b"cpu,host=a usage=0.5\n");
+/// ```
+#[derive(Debug, Clone)]
+pub struct LineProtocolEncoder {
+    validate: bool,
+    sort_fields: bool,
+    precision: Precision,
+}
+
+impl LineProtocolEncoder {
+    /// Create a new encoder, writing timestamps in nanoseconds
+    pub fn new() -> Self {
+        LineProtocolEncoder {
+            validate: false,
+            sort_fields: false,
+            precision: Precision::Nanoseconds,
+        }
+    }
+
+    /// Set the precision that absolute timestamps are converted to.
+    ///
+    /// It must match the precision of the write, raw `i64` timestamps are written unchanged.
+    pub fn set_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Validate every point before encoding it, see `Point::validate`
+    pub fn set_validation(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    /// Also write fields sorted by key, making the whole line canonical
+    pub fn set_sort_fields(mut self, sort_fields: bool) -> Self {
+        self.sort_fields = sort_fields;
+        self
+    }
+
+    /// Encode points into any `io::Write`, one line per point.
+    ///
+    /// If a point fails validation, the points before it have already been written.
+    pub fn encode<'a, W: io::Write>(
+        &self,
+        points: impl IntoIterator<Item = impl Borrow<Point<'a>>>,
+        writer: &mut W,
+    ) -> Result<(), Error> {
+        for (index, point) in points.into_iter().enumerate() {
+            self.encode_at(index, point.borrow(), writer)?;
+        }
+        Ok(())
+    }
+
+    /// Encode a single point into any `io::Write`
+    pub fn encode_point<W: io::Write>(&self, point: &Point, writer: &mut W) -> Result<(), Error> {
+        self.encode_at(0, point, writer)
+    }
+
+    /// Encode points at the end of a `BytesMut`
+    pub fn encode_to_bytes<'a>(
+        &self,
+        points: impl IntoIterator<Item = impl Borrow<Point<'a>>>,
+        buf: &mut BytesMut,
+    ) -> Result<(), Error> {
+        self.encode(points, &mut buf.writer())
+    }
+
+    fn encode_at<W: io::Write>(
+        &self,
+        index: usize,
+        point: &Point,
+        writer: &mut W,
+    ) -> Result<(), Error> {
+        if self.validate {
+            point.validate_at(index)?;
+        }
+        self.write_point(writer, point)?;
+        Ok(())
+    }
+
+    fn write_point<W: io::Write>(&self, w: &mut W, point: &Point) -> io::Result<()> {
+        write_escaped(w, &point.measurement, b", ")?;
+
+        if point.tags.len() > 1 {
+            for (tag, value) in sorted(&point.tags) {
+                write_tag(w, tag, value)?;
+            }
+        } else {
+            for (tag, value) in &point.tags {
+                write_tag(w, tag, value)?;
+            }
+        }
+
+        if self.sort_fields && point.fields.len() > 1 {
+            for (i, (field, value)) in sorted(&point.fields).into_iter().enumerate() {
+                write_field(w, i, field, value)?;
+            }
+        } else {
+            for (i, (field, value)) in point.fields.iter().enumerate() {
+                write_field(w, i, field, value)?;
+            }
+        }
+
+        if let Some(t) = point.timestamp {
+            w.write_all(b" ")?;
+            write_integer(w, t.to_precision(self.precision))?;
+        }
+
+        w.write_all(b"\n")
+    }
+}
+
+impl Default for LineProtocolEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn sorted<'m, 'a>(map: &'m HashMap<String, Value<'a>>) -> Vec<(&'m String, &'m Value<'a>)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+fn write_tag<W: io::Write>(w: &mut W, tag: &str, value: &Value) -> io::Result<()> {
+    w.write_all(b",")?;
+    write_escaped(w, tag, b",= ")?;
+    w.write_all(b"=")?;
+
+    match value {
+        Value::String(s) => write_escaped(w, s, b",= ")?,
+        Value::Float(f) => write!(w, "{}", f)?,
+        Value::Integer(i) => write_integer(w, *i)?,
+        Value::UInteger(u) => write_integer(w, *u)?,
+        Value::Boolean(b) => write_bool(w, *b)?,
+    }
+    Ok(())
+}
+
+fn write_field<W: io::Write>(w: &mut W, i: usize, field: &str, value: &Value) -> io::Result<()> {
+    w.write_all(if i == 0 { b" " } else { b"," })?;
+    write_escaped(w, field, b",= ")?;
+    w.write_all(b"=")?;
+
+    match value {
+        Value::String(s) => {
+            w.write_all(b"\"")?;
+            write_escaped(w, s, b"\\\"")?;
+            w.write_all(b"\"")?;
+        }
+        Value::Float(f) => write!(w, "{}", f)?,
+        Value::Integer(i) => {
+            write_integer(w, *i)?;
+            w.write_all(b"i")?;
+        }
+        Value::UInteger(u) => {
+            write_integer(w, *u)?;
+            w.write_all(b"u")?;
+        }
+        Value::Boolean(b) => write_bool(w, *b)?,
+    }
+    Ok(())
+}
+
+/// Write `value`, putting a backslash in front of every byte in `special`
+#[inline]
+fn write_escaped<W: io::Write>(w: &mut W, value: &str, special: &[u8]) -> io::Result<()> {
+    let bytes = value.as_bytes();
+    let mut start = 0;
+
+    for (i, b) in bytes.iter().enumerate() {
+        if special.contains(b) {
+            w.write_all(&bytes[start..i])?;
+            w.write_all(&[b'\\', *b])?;
+            start = i + 1;
+        }
+    }
+
+    w.write_all(&bytes[start..])
+}
+
+#[inline]
+fn write_integer<W: io::Write>(w: &mut W, value: impl itoa::Integer) -> io::Result<()> {
+    w.write_all(itoa::Buffer::new().format(value).as_bytes())
+}
+
+#[inline]
+fn write_bool<W: io::Write>(w: &mut W, value: bool) -> io::Result<()> {
+    w.write_all(if value { b"true" } else { b"false" })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Points;
+
+    fn escaped(value: &str, special: &[u8]) -> String {
+        let mut buf = Vec::new();
+        write_escaped(&mut buf, value, special).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    fn encode(points: &Points) -> String {
+        let mut buf = Vec::new();
+        LineProtocolEncoder::new().encode(points, &mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    /// The `String` based serialization the encoder replaced, with tags sorted
+    fn legacy_line_serialization(points: &Points) -> String {
+        let mut line = String::new();
+
+        for point in points {
+            line.push_str(&point.measurement.replace(',', "\\,").replace(' ', "\\ "));
+
+            let escape = |s: &str| {
+                s.replace(',', "\\,")
+                    .replace('=', "\\=")
+                    .replace(' ', "\\ ")
+            };
+
+            let mut tags: Vec<_> = point.tags.iter().collect();
+            tags.sort_by(|a, b| a.0.cmp(b.0));
+
+            for (tag, value) in tags {
+                line.push(',');
+                line.push_str(&escape(tag));
+                line.push('=');
+
+                match value {
+                    Value::String(s) => line.push_str(&escape(s)),
+                    Value::Float(f) => line.push_str(f.to_string().as_str()),
+                    Value::Integer(i) => line.push_str(i.to_string().as_str()),
+                    Value::UInteger(u) => line.push_str(u.to_string().as_str()),
+                    Value::Boolean(b) => line.push_str(&b.to_string()),
+                }
+            }
+
+            for (i, (field, value)) in point.fields.iter().enumerate() {
+                line.push(if i == 0 { ' ' } else { ',' });
+                line.push_str(&escape(field));
+                line.push('=');
+
+                match value {
+                    Value::String(s) => line.push_str(&format!(
+                        "\"{}\"",
+                        s.replace('\\', "\\\\").replace('\"', "\\\"")
+                    )),
+                    Value::Float(f) => line.push_str(&f.to_string()),
+                    Value::Integer(i) => line.push_str(&format!("{i}i")),
+                    Value::UInteger(u) => line.push_str(&format!("{u}u")),
+                    Value::Boolean(b) => line.push_str(&b.to_string()),
+                }
+            }
+
+            if let Some(t) = point.timestamp {
+                line.push(' ');
+                line.push_str(&t.to_precision(Precision::Nanoseconds).to_string());
+            }
+
+            line.push('\n')
+        }
+
+        line
+    }
+
+    #[test]
+    fn line_serialization_test() {
+        let point = Point::new("test")
+            .add_field("somefield", Value::Integer(65))
+            .add_tag("sometag", Value::Boolean(false));
+        let points = Points::new(point);
+
+        assert_eq!(encode(&points), "test,sometag=false somefield=65i\n")
+    }
+
+    #[test]
+    fn line_serialization_unsigned_test() {
+        let point = Point::new("test")
+            .add_field("counter", u64::MAX)
+            .add_tag("shard", 3u32);
+        let points = Points::new(point);
+
+        assert_eq!(
+            encode(&points),
+            "test,shard=3 counter=18446744073709551615u\n"
+        )
+    }
+
+    #[test]
+    fn matches_legacy_serialization_test() {
+        let points = Points::create_new(vec![
+            Point::new("plain").add_field("f", 1.0),
+            Point::new("we ird,name")
+                .add_tag("t a,g", "v=a l,ue")
+                .add_tag("float", 12.6)
+                .add_tag("int", -7)
+                .add_tag("bool", true)
+                .add_field("s", "\\\"fda \"q\" \\ end")
+                .add_field("big", 1e21)
+                .add_field("small", 1e-7)
+                .add_field("neg", i64::MIN)
+                .add_field("u", 7u64)
+                .add_field("b", false)
+                .add_timestamp(-1),
+            Point::new("ünïcødé").add_field("ключ", "значение"),
+        ]);
+
+        assert_eq!(encode(&points), legacy_line_serialization(&points));
+    }
+
+    #[test]
+    fn sorted_tags_test() {
+        let point = Point::new("m")
+            .add_tag("zone", "z")
+            .add_tag("host", "h")
+            .add_tag("Region", "r")
+            .add_tag("app", "a")
+            .add_field("f", 1);
+
+        assert_eq!(
+            encode(&Points::new(point)),
+            "m,Region=r,app=a,host=h,zone=z f=1i\n"
+        );
+    }
+
+    #[test]
+    fn sorted_fields_test() {
+        let point = Point::new("m")
+            .add_tag("host", "h")
+            .add_field("c", 3)
+            .add_field("a", 1)
+            .add_field("b", 2)
+            .add_timestamp(1);
+        let mut buf = Vec::new();
+
+        LineProtocolEncoder::new()
+            .set_sort_fields(true)
+            .encode_point(&point, &mut buf)
+            .unwrap();
+
+        assert_eq!(buf, b"m,host=h a=1i,b=2i,c=3i 1\n");
+    }
+
+    #[test]
+    fn timestamp_precision_test() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let time = UNIX_EPOCH + Duration::from_millis(1_600_000_000_123);
+        let points = Points::create_new(vec![
+            Point::new("m").add_field("f", 1).add_timestamp(time),
+            Point::new("m").add_field("f", 1).add_timestamp(7),
+        ]);
+        let mut buf = Vec::new();
+
+        LineProtocolEncoder::new()
+            .set_precision(Precision::Seconds)
+            .encode(&points, &mut buf)
+            .unwrap();
+        assert_eq!(buf, b"m f=1i 1600000000\nm f=1i 7\n");
+
+        assert_eq!(encode(&points), "m f=1i 1600000000123000000\nm f=1i 7\n");
+    }
+
+    #[test]
+    fn encode_to_bytes_test() {
+        let points = Points::new(Point::new("m").add_field("f", 1).add_timestamp(10));
+        let mut buf = BytesMut::from(&b"existing\n"[..]);
+
+        LineProtocolEncoder::new()
+            .encode_to_bytes(&points, &mut buf)
+            .unwrap();
+
+        assert_eq!(&buf[..], b"existing\nm f=1i 10\n");
+    }
+
+    #[test]
+    fn encode_validation_test() {
+        let points = Points::create_new(vec![
+            Point::new("m").add_field("f", 1),
+            Point::new("m").add_field("f", f64::NAN),
+        ]);
+        let mut buf = Vec::new();
+
+        let err = LineProtocolEncoder::new()
+            .set_validation(true)
+            .encode(&points, &mut buf)
+            .unwrap_err();
+
+        assert_eq!(
+            err,
+            Error::InvalidPoint {
+                index: 1,
+                key: "f".to_string(),
+                reason: "NaN or infinite float".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn escape_keys_and_tags_test() {
+        assert_eq!(
+            escaped("foo, hello=world", b",= "),
+            "foo\\,\\ hello\\=world"
+        )
+    }
+
+    #[test]
+    fn escape_measurement_test() {
+        assert_eq!(escaped("foo, hello", b", "), "foo\\,\\ hello")
+    }
+
+    #[test]
+    fn escape_string_field_value_test() {
+        assert_eq!(escaped("\"foo", b"\\\""), "\\\"foo");
+        assert_eq!(escaped(r#"C:\dir\"x""#, b"\\\""), r#"C:\\dir\\\"x\""#)
+    }
+}
@@ -3,57 +3,486 @@ use std::error::Error as StdError;
 use std::fmt;
 use std::io;
 
+use crate::{retry, serialization};
+
+/// The kind of request an error comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum RequestKind {
+    /// A write of line protocol
+    Write,
+    /// A query, including the statements managing databases, users and retention policies
+    Query,
+}
+
+impl fmt::Display for RequestKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RequestKind::Write => write!(f, "write"),
+            RequestKind::Query => write!(f, "query"),
+        }
+    }
+}
+
 /// The error of influxdb client
 #[derive(Debug, Deserialize, PartialEq, Eq, Serialize)]
 pub enum Error {
     /// Syntax error, some is bug, some is SQL error. If it's a bug, welcome to PR.
-    SyntaxError(String),
+    SyntaxError {
+        /// http status code of the response
+        status: u16,
+        /// the request that failed
+        kind: RequestKind,
+        /// error message of the server
+        message: String,
+    },
     /// Invalid credentials
-    InvalidCredentials(String),
+    InvalidCredentials {
+        /// http status code of the response
+        status: u16,
+        /// the request that failed
+        kind: RequestKind,
+        /// error message of the server
+        message: String,
+    },
     /// The specified database does not exist
-    DataBaseDoesNotExist(String),
+    DataBaseDoesNotExist {
+        /// http status code of the response
+        status: u16,
+        /// the request that failed
+        kind: RequestKind,
+        /// error message of the server
+        message: String,
+    },
     /// The specified retention policy does not exist
-    RetentionPolicyDoesNotExist(String),
+    RetentionPolicyDoesNotExist {
+        /// http status code of the response
+        status: u16,
+        /// the request that failed
+        kind: RequestKind,
+        /// error message of the server
+        message: String,
+    },
+    /// Some points of a write were rejected, the others were written
+    PartialWrite {
+        /// http status code of the response
+        status: u16,
+        /// error message of the server
+        message: String,
+        /// number of rejected points, when the server reports it
+        dropped: Option<usize>,
+    },
+    /// Some points of a write have a field of another type than the one already stored,
+    /// they were rejected and the others were written
+    FieldTypeConflict {
+        /// http status code of the response
+        status: u16,
+        /// error message of the server
+        message: String,
+        /// number of rejected points, when the server reports it
+        dropped: Option<usize>,
+    },
+    /// Some points of a write are older than the retention policy keeps,
+    /// they were rejected and the others were written
+    PointsBeyondRetentionPolicy {
+        /// http status code of the response
+        status: u16,
+        /// error message of the server
+        message: String,
+        /// number of rejected points, when the server reports it
+        dropped: Option<usize>,
+    },
+    /// Any other error status of the server
+    Server {
+        /// http status code of the response
+        status: u16,
+        /// the request that failed
+        kind: RequestKind,
+        /// error message of the server
+        message: String,
+    },
+    /// The request timed out, it may or may not have been applied
+    Timeout(String),
+    /// The connection to the server failed, the request was not sent
+    Connection(String),
     /// Some error on build url or io.
     Communication(String),
-    /// Some other error, I don't expect
-    Unknow(String),
+    /// Invalid line protocol
+    ParseError {
+        /// 1-based line number
+        line: usize,
+        /// what is wrong with the line
+        message: String,
+    },
+    /// A point that would not be accepted by the server
+    InvalidPoint {
+        /// position of the point in the written iterator, 0 for a single point
+        index: usize,
+        /// the offending measurement, tag key or field key
+        key: String,
+        /// why the point is invalid
+        reason: String,
+    },
+    /// The response could not be decoded into the requested type
+    DeserializeError(String),
+    /// An argument that can't be turned into a valid statement
+    InvalidArgument(String),
+    /// The queue of a `BatchWriter` is full
+    BatchQueueFull,
+    /// The `BatchWriter` is closed and accepts no more points
+    BatchWriterClosed,
+}
+
+impl Error {
+    /// Classify an error response of the server
+    pub(crate) fn from_response(status: u16, body: &str, kind: RequestKind) -> Self {
+        let message = serialization::conversion(body);
+        let lower = message.to_lowercase();
+        let dropped = || {
+            let count = &message[message.rfind("dropped=")? + "dropped=".len()..];
+            count
+                .split(|c: char| !c.is_ascii_digit())
+                .next()?
+                .parse()
+                .ok()
+        };
+
+        match status {
+            _ if lower.contains("field type conflict") => Error::FieldTypeConflict {
+                status,
+                dropped: dropped(),
+                message,
+            },
+            _ if lower.contains("points beyond retention policy") => {
+                Error::PointsBeyondRetentionPolicy {
+                    status,
+                    dropped: dropped(),
+                    message,
+                }
+            }
+            _ if lower.starts_with("partial write") => Error::PartialWrite {
+                status,
+                dropped: dropped(),
+                message,
+            },
+            400 => Error::SyntaxError {
+                status,
+                kind,
+                message,
+            },
+            401 | 403 => Error::InvalidCredentials {
+                status,
+                kind,
+                message: if message.is_empty() {
+                    "Invalid authentication credentials.".to_string()
+                } else {
+                    message
+                },
+            },
+            _ if lower.contains("database not found") => Error::DataBaseDoesNotExist {
+                status,
+                kind,
+                message,
+            },
+            _ if lower.contains("retention policy not found") => {
+                Error::RetentionPolicyDoesNotExist {
+                    status,
+                    kind,
+                    message,
+                }
+            }
+            404 => Error::DataBaseDoesNotExist {
+                status,
+                kind,
+                message,
+            },
+            _ => Error::Server {
+                status,
+                kind,
+                message,
+            },
+        }
+    }
+
+    /// The http status code of the response, for errors returned by the server
+    pub fn status(&self) -> Option<u16> {
+        match *self {
+            Error::SyntaxError { status, .. }
+            | Error::InvalidCredentials { status, .. }
+            | Error::DataBaseDoesNotExist { status, .. }
+            | Error::RetentionPolicyDoesNotExist { status, .. }
+            | Error::PartialWrite { status, .. }
+            | Error::FieldTypeConflict { status, .. }
+            | Error::PointsBeyondRetentionPolicy { status, .. }
+            | Error::Server { status, .. } => Some(status),
+            _ => None,
+        }
+    }
+
+    /// The kind of the failed request, for errors returned by the server
+    pub fn kind(&self) -> Option<RequestKind> {
+        match *self {
+            Error::SyntaxError { kind, .. }
+            | Error::InvalidCredentials { kind, .. }
+            | Error::DataBaseDoesNotExist { kind, .. }
+            | Error::RetentionPolicyDoesNotExist { kind, .. }
+            | Error::Server { kind, .. } => Some(kind),
+            Error::PartialWrite { .. }
+            | Error::FieldTypeConflict { .. }
+            | Error::PointsBeyondRetentionPolicy { .. } => Some(RequestKind::Write),
+            _ => None,
+        }
+    }
+
+    /// Whether the same request may succeed when sent again, as `RetryPolicy` decides:
+    /// `Connection` failures, `429`, `503` and "hinted handoff queue full".
+    ///
+    /// Timeouts are not retryable, the request may have been applied.
+    pub fn is_retryable(&self) -> bool {
+        match *self {
+            Error::Connection(_) => true,
+            Error::SyntaxError {
+                status,
+                ref message,
+                ..
+            }
+            | Error::InvalidCredentials {
+                status,
+                ref message,
+                ..
+            }
+            | Error::DataBaseDoesNotExist {
+                status,
+                ref message,
+                ..
+            }
+            | Error::RetentionPolicyDoesNotExist {
+                status,
+                ref message,
+                ..
+            }
+            | Error::Server {
+                status,
+                ref message,
+                ..
+            } => retry::is_transient(status, message),
+            _ => false,
+        }
+    }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Error::SyntaxError(ref t) => write!(f, "{}", t),
-            Error::InvalidCredentials(ref t) => write!(f, "{}", t),
-            Error::DataBaseDoesNotExist(ref t) => write!(f, "{}", t),
-            Error::RetentionPolicyDoesNotExist(ref t) => write!(f, "{}", t),
+            Error::SyntaxError {
+                status,
+                kind,
+                ref message,
+            }
+            | Error::InvalidCredentials {
+                status,
+                kind,
+                ref message,
+            }
+            | Error::DataBaseDoesNotExist {
+                status,
+                kind,
+                ref message,
+            }
+            | Error::RetentionPolicyDoesNotExist {
+                status,
+                kind,
+                ref message,
+            }
+            | Error::Server {
+                status,
+                kind,
+                ref message,
+            } => write!(f, "{} failed with status {}: {}", kind, status, message),
+            Error::PartialWrite {
+                status,
+                ref message,
+                ..
+            }
+            | Error::FieldTypeConflict {
+                status,
+                ref message,
+                ..
+            }
+            | Error::PointsBeyondRetentionPolicy {
+                status,
+                ref message,
+                ..
+            } => write!(f, "write failed with status {}: {}", status, message),
+            Error::Timeout(ref t) => write!(f, "{}", t),
+            Error::Connection(ref t) => write!(f, "{}", t),
             Error::Communication(ref t) => write!(f, "{}", t),
-            Error::Unknow(ref t) => write!(f, "{}", t),
+            Error::ParseError {
+                ref line,
+                ref message,
+            } => write!(f, "line {}: {}", line, message),
+            Error::InvalidPoint {
+                ref index,
+                ref key,
+                ref reason,
+            } => write!(f, "point {}, key '{}': {}", index, key, reason),
+            Error::DeserializeError(ref t) => write!(f, "{}", t),
+            Error::InvalidArgument(ref t) => write!(f, "{}", t),
+            Error::BatchQueueFull => write!(f, "The batch writer queue is full"),
+            Error::BatchWriterClosed => write!(f, "The batch writer is closed"),
         }
     }
 }
 
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Self {
-        Error::Communication(format!("{}", err))
+        match err.kind() {
+            io::ErrorKind::TimedOut => Error::Timeout(format!("{}", err)),
+            _ => Error::Communication(format!("{}", err)),
+        }
     }
 }
 
 impl From<reqwest::Error> for Error {
     fn from(err: reqwest::Error) -> Self {
-        Error::Communication(format!("{}", err))
+        if err.is_timeout() {
+            Error::Timeout(format!("{}", err))
+        } else if err.is_connect() {
+            Error::Connection(format!("{}", err))
+        } else if err.is_decode() {
+            Error::DeserializeError(format!("{}", err))
+        } else {
+            Error::Communication(format!("{}", err))
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::DeserializeError(format!("{}", err))
     }
 }
 
 impl StdError for Error {
     fn description(&self) -> &str {
         match *self {
-            Error::SyntaxError(ref t) => t,
-            Error::InvalidCredentials(ref t) => t,
-            Error::DataBaseDoesNotExist(ref t) => t,
-            Error::RetentionPolicyDoesNotExist(ref t) => t,
+            Error::SyntaxError { ref message, .. } => message,
+            Error::InvalidCredentials { ref message, .. } => message,
+            Error::DataBaseDoesNotExist { ref message, .. } => message,
+            Error::RetentionPolicyDoesNotExist { ref message, .. } => message,
+            Error::PartialWrite { ref message, .. } => message,
+            Error::FieldTypeConflict { ref message, .. } => message,
+            Error::PointsBeyondRetentionPolicy { ref message, .. } => message,
+            Error::Server { ref message, .. } => message,
+            Error::Timeout(ref t) => t,
+            Error::Connection(ref t) => t,
             Error::Communication(ref t) => t,
-            Error::Unknow(ref t) => t,
+            Error::ParseError { ref message, .. } => message,
+            Error::InvalidPoint { ref reason, .. } => reason,
+            Error::DeserializeError(ref t) => t,
+            Error::InvalidArgument(ref t) => t,
+            Error::BatchQueueFull => "The batch writer queue is full",
+            Error::BatchWriterClosed => "The batch writer is closed",
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Error, RequestKind};
+
+    #[test]
+    fn classify_responses() {
+        let err = Error::from_response(
+            400,
+            r#"{"error":"partial write: field type conflict: input field \"value\" on measurement \"cpu\" is type integer, already exists as type float dropped=2"}"#,
+            RequestKind::Write,
+        );
+        assert_eq!(
+            err,
+            Error::FieldTypeConflict {
+                status: 400,
+                message: "partial write: field type conflict: input field \"value\" on measurement \"cpu\" is type integer, already exists as type float dropped=2".to_string(),
+                dropped: Some(2),
+            }
+        );
+        assert!(!err.is_retryable());
+
+        assert!(matches!(
+            Error::from_response(
+                400,
+                r#"{"error":"partial write: points beyond retention policy dropped=1"}"#,
+                RequestKind::Write
+            ),
+            Error::PointsBeyondRetentionPolicy {
+                dropped: Some(1),
+                ..
+            }
+        ));
+        assert!(matches!(
+            Error::from_response(
+                400,
+                r#"{"error":"unable to parse 'cpu value=': missing field value"}"#,
+                RequestKind::Write
+            ),
+            Error::SyntaxError { status: 400, .. }
+        ));
+        assert!(matches!(
+            Error::from_response(
+                404,
+                r#"{"error":"database not found: \"foo\""}"#,
+                RequestKind::Write
+            ),
+            Error::DataBaseDoesNotExist { ref message, .. } if message == "database not found: \"foo\""
+        ));
+        assert!(matches!(
+            Error::from_response(
+                500,
+                r#"{"error":"retention policy not found: short"}"#,
+                RequestKind::Write
+            ),
+            Error::RetentionPolicyDoesNotExist { status: 500, .. }
+        ));
+        assert!(matches!(
+            Error::from_response(500, r#"{"error":"timeout"}"#, RequestKind::Query),
+            Error::Server {
+                status: 500,
+                kind: RequestKind::Query,
+                ..
+            }
+        ));
+
+        assert!(matches!(
+            Error::from_response(
+                404,
+                r#"{"code":"not found","message":"bucket \"foo\" not found"}"#,
+                RequestKind::Write
+            ),
+            Error::DataBaseDoesNotExist { ref message, .. } if message == "bucket \"foo\" not found"
+        ));
+        assert!(matches!(
+            Error::from_response(
+                422,
+                r#"{"code":"unprocessable entity","message":"failure writing points to database: partial write: points beyond retention policy dropped=3"}"#,
+                RequestKind::Write
+            ),
+            Error::PointsBeyondRetentionPolicy {
+                status: 422,
+                dropped: Some(3),
+                ..
+            }
+        ));
+
+        let err = Error::from_response(503, "", RequestKind::Query);
+        assert!(err.is_retryable());
+        assert_eq!(err.status(), Some(503));
+        assert_eq!(err.kind(), Some(RequestKind::Query));
+        assert!(Error::from_response(
+            500,
+            r#"{"error":"write failed: hinted handoff queue full"}"#,
+            RequestKind::Write
+        )
+        .is_retryable());
+        assert!(Error::Connection("connection refused".to_string()).is_retryable());
+        assert!(!Error::Communication("builder error".to_string()).is_retryable());
+    }
+}
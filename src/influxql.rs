@@ -0,0 +1,831 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{convert::TryFrom, fmt, str::FromStr, time::Duration};
+
+use crate::{
+    error::Error,
+    serialization::{format_duration, quote_ident, quote_literal, quote_regex},
+    Precision, Timestamp,
+};
+
+/// An InfluxQL duration literal, such as `1h30m`, or `INF` for retention policies
+/// that keep data forever.
+///
+/// ```
+/// use influx_db_client::influxql::InfluxDuration;
+/// use std::time::Duration;
+///
+/// let duration: InfluxDuration = "1h30m".parse().unwrap();
+/// assert_eq!(duration, InfluxDuration::Finite(Duration::from_secs(5400)));
+/// assert_eq!(duration.to_string(), "1h30m");
+/// assert_eq!("inf".parse::<InfluxDuration>().unwrap(), InfluxDuration::Infinite);
+/// assert!("1y".parse::<InfluxDuration>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InfluxDuration {
+    /// `INF`
+    Infinite,
+    /// A finite duration
+    Finite(Duration),
+}
+
+impl From<Duration> for InfluxDuration {
+    fn from(d: Duration) -> Self {
+        InfluxDuration::Finite(d)
+    }
+}
+
+impl fmt::Display for InfluxDuration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InfluxDuration::Infinite => write!(f, "INF"),
+            InfluxDuration::Finite(d) => write!(f, "{}", format_duration(d)),
+        }
+    }
+}
+
+impl FromStr for InfluxDuration {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        // Longer units first, so that `ms` is not read as `m`
+        const UNITS: [(&str, u128); 11] = [
+            ("ns", 1),
+            ("us", 1_000),
+            ("\u{b5}s", 1_000),
+            ("ms", 1_000_000),
+            ("u", 1_000),
+            ("\u{b5}", 1_000),
+            ("s", 1_000_000_000),
+            ("m", 60 * 1_000_000_000),
+            ("h", 3600 * 1_000_000_000),
+            ("d", 24 * 3600 * 1_000_000_000),
+            ("w", 7 * 24 * 3600 * 1_000_000_000),
+        ];
+
+        let invalid = || Error::InvalidArgument(format!("Invalid duration '{}'", s));
+        if s.eq_ignore_ascii_case("inf") {
+            return Ok(InfluxDuration::Infinite);
+        }
+        if s.is_empty() {
+            return Err(invalid());
+        }
+
+        let mut rest = s;
+        let mut nanos: u128 = 0;
+        while !rest.is_empty() {
+            let digits = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let n: u128 = rest[..digits].parse().map_err(|_| invalid())?;
+            rest = &rest[digits..];
+
+            let (unit, size) = UNITS
+                .iter()
+                .find(|(unit, _)| rest.starts_with(unit))
+                .ok_or_else(invalid)?;
+            rest = &rest[unit.len()..];
+            nanos = n
+                .checked_mul(*size)
+                .and_then(|n| nanos.checked_add(n))
+                .ok_or_else(invalid)?;
+        }
+
+        let secs = u64::try_from(nanos / 1_000_000_000).map_err(|_| invalid())?;
+        Ok(InfluxDuration::Finite(Duration::new(
+            secs,
+            (nanos % 1_000_000_000) as u32,
+        )))
+    }
+}
+
+impl Serialize for InfluxDuration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for InfluxDuration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+/// A measurement, optionally qualified by database and retention policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Measurement {
+    database: Option<String>,
+    retention_policy: Option<String>,
+    name: Name,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Name {
+    Ident(String),
+    Regex(String),
+    Backreference,
+}
+
+impl Measurement {
+    /// The measurement with this name
+    pub fn new<T: Into<String>>(name: T) -> Self {
+        Measurement {
+            database: None,
+            retention_policy: None,
+            name: Name::Ident(name.into()),
+        }
+    }
+
+    /// All measurements matching a regular expression
+    pub fn regex<T: Into<String>>(pattern: T) -> Self {
+        Measurement {
+            database: None,
+            retention_policy: None,
+            name: Name::Regex(pattern.into()),
+        }
+    }
+
+    /// `:MEASUREMENT`, the source measurement of each point, only valid in an `INTO` clause
+    pub fn backreference() -> Self {
+        Measurement {
+            database: None,
+            retention_policy: None,
+            name: Name::Backreference,
+        }
+    }
+
+    /// Qualify the measurement with a database
+    pub fn set_database<T: Into<String>>(mut self, database: T) -> Self {
+        self.database = Some(database.into());
+        self
+    }
+
+    /// Qualify the measurement with a retention policy
+    pub fn set_retention_policy<T: Into<String>>(mut self, rp: T) -> Self {
+        self.retention_policy = Some(rp.into());
+        self
+    }
+}
+
+impl<'a> From<&'a str> for Measurement {
+    fn from(name: &'a str) -> Self {
+        Measurement::new(name)
+    }
+}
+
+impl From<String> for Measurement {
+    fn from(name: String) -> Self {
+        Measurement::new(name)
+    }
+}
+
+impl fmt::Display for Measurement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.database, &self.retention_policy) {
+            (Some(db), Some(rp)) => write!(f, "{}.{}.", quote_ident(db), quote_ident(rp))?,
+            (Some(db), None) => write!(f, "{}..", quote_ident(db))?,
+            (None, Some(rp)) => write!(f, "{}.", quote_ident(rp))?,
+            (None, None) => (),
+        }
+        match self.name {
+            Name::Ident(ref name) => write!(f, "{}", quote_ident(name)),
+            Name::Regex(ref pattern) => write!(f, "{}", quote_regex(pattern)),
+            Name::Backreference => write!(f, ":MEASUREMENT"),
+        }
+    }
+}
+
+/// Aggregate and selector functions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregate {
+    /// count
+    Count,
+    /// distinct
+    Distinct,
+    /// integral
+    Integral,
+    /// mean
+    Mean,
+    /// median
+    Median,
+    /// mode
+    Mode,
+    /// spread
+    Spread,
+    /// stddev
+    Stddev,
+    /// sum
+    Sum,
+    /// first
+    First,
+    /// last
+    Last,
+    /// max
+    Max,
+    /// min
+    Min,
+    /// percentile, from 0 to 100
+    Percentile(f64),
+    /// sample of N points
+    Sample(u64),
+}
+
+impl Aggregate {
+    fn name(&self) -> &'static str {
+        match *self {
+            Aggregate::Count => "count",
+            Aggregate::Distinct => "distinct",
+            Aggregate::Integral => "integral",
+            Aggregate::Mean => "mean",
+            Aggregate::Median => "median",
+            Aggregate::Mode => "mode",
+            Aggregate::Spread => "spread",
+            Aggregate::Stddev => "stddev",
+            Aggregate::Sum => "sum",
+            Aggregate::First => "first",
+            Aggregate::Last => "last",
+            Aggregate::Max => "max",
+            Aggregate::Min => "min",
+            Aggregate::Percentile(_) => "percentile",
+            Aggregate::Sample(_) => "sample",
+        }
+    }
+}
+
+/// A selected field, column or expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    expr: String,
+    alias: Option<String>,
+}
+
+impl Field {
+    /// A field or tag key
+    pub fn new(key: &str) -> Self {
+        Field {
+            expr: quote_key(key),
+            alias: None,
+        }
+    }
+
+    /// `*`, all fields and tags
+    pub fn all() -> Self {
+        Field {
+            expr: "*".to_string(),
+            alias: None,
+        }
+    }
+
+    /// A function of a field key, `*` for all fields
+    pub fn aggregate(function: Aggregate, key: &str) -> Self {
+        let expr = match function {
+            Aggregate::Percentile(n) => format!("{}({}, {})", function.name(), quote_key(key), n),
+            Aggregate::Sample(n) => format!("{}({}, {})", function.name(), quote_key(key), n),
+            _ => format!("{}({})", function.name(), quote_key(key)),
+        };
+        Field { expr, alias: None }
+    }
+
+    /// An expression written as is, nothing in it is quoted
+    pub fn raw<T: Into<String>>(expr: T) -> Self {
+        Field {
+            expr: expr.into(),
+            alias: None,
+        }
+    }
+
+    /// Name the column of this field in the result
+    pub fn set_alias<T: Into<String>>(mut self, alias: T) -> Self {
+        self.alias = Some(alias.into());
+        self
+    }
+}
+
+impl<'a> From<&'a str> for Field {
+    fn from(key: &'a str) -> Self {
+        Field::new(key)
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.alias {
+            Some(ref alias) => write!(f, "{} AS {}", self.expr, quote_ident(alias)),
+            None => write!(f, "{}", self.expr),
+        }
+    }
+}
+
+fn quote_key(key: &str) -> String {
+    if key == "*" {
+        key.to_string()
+    } else {
+        quote_ident(key)
+    }
+}
+
+/// A bound of a time predicate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Time {
+    /// A point in time, a `Timestamp::Raw` must be in nanoseconds
+    At(Timestamp),
+    /// `now()`
+    Now,
+    /// `now() - <duration>`
+    Ago(Duration),
+    /// `now() + <duration>`
+    FromNow(Duration),
+}
+
+impl From<Timestamp> for Time {
+    fn from(t: Timestamp) -> Self {
+        Time::At(t)
+    }
+}
+
+impl From<std::time::SystemTime> for Time {
+    fn from(t: std::time::SystemTime) -> Self {
+        Time::At(t.into())
+    }
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Time::At(t) => write!(f, "{}", t.to_precision(Precision::Nanoseconds)),
+            Time::Now => write!(f, "now()"),
+            Time::Ago(d) => write!(f, "now() - {}", format_duration(d)),
+            Time::FromNow(d) => write!(f, "now() + {}", format_duration(d)),
+        }
+    }
+}
+
+/// A `WHERE` predicate on tags and time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition(Expr);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    /// `"key" <op> <quoted value or regex>`
+    Tag(String, &'static str, String),
+    /// `time <op> <bound>`
+    Time(&'static str, Time),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+impl Condition {
+    fn tag(key: &str, op: &'static str, value: String) -> Self {
+        Condition(Expr::Tag(key.to_string(), op, value))
+    }
+
+    fn time(op: &'static str, bound: Time) -> Self {
+        Condition(Expr::Time(op, bound))
+    }
+
+    /// The tag equals a value
+    pub fn tag_eq(key: &str, value: &str) -> Self {
+        Condition::tag(key, "=", quote_literal(value))
+    }
+
+    /// The tag differs from a value
+    pub fn tag_ne(key: &str, value: &str) -> Self {
+        Condition::tag(key, "!=", quote_literal(value))
+    }
+
+    /// The tag matches a regular expression
+    pub fn tag_matches(key: &str, pattern: &str) -> Self {
+        Condition::tag(key, "=~", quote_regex(pattern))
+    }
+
+    /// The tag doesn't match a regular expression
+    pub fn tag_not_matches(key: &str, pattern: &str) -> Self {
+        Condition::tag(key, "!~", quote_regex(pattern))
+    }
+
+    /// Points after the bound
+    pub fn time_gt<T: Into<Time>>(bound: T) -> Self {
+        Condition::time(">", bound.into())
+    }
+
+    /// Points at or after the bound
+    pub fn time_ge<T: Into<Time>>(bound: T) -> Self {
+        Condition::time(">=", bound.into())
+    }
+
+    /// Points before the bound
+    pub fn time_lt<T: Into<Time>>(bound: T) -> Self {
+        Condition::time("<", bound.into())
+    }
+
+    /// Points at or before the bound
+    pub fn time_le<T: Into<Time>>(bound: T) -> Self {
+        Condition::time("<=", bound.into())
+    }
+
+    /// Both this and the other condition hold
+    pub fn and(self, other: Condition) -> Self {
+        Condition(Expr::And(Box::new(self), Box::new(other)))
+    }
+
+    /// This or the other condition holds
+    pub fn or(self, other: Condition) -> Self {
+        Condition(Expr::Or(Box::new(self), Box::new(other)))
+    }
+
+    /// Whether a time predicate is part of the condition
+    pub fn has_time(&self) -> bool {
+        match self.0 {
+            Expr::Tag(..) => false,
+            Expr::Time(..) => true,
+            Expr::And(ref a, ref b) | Expr::Or(ref a, ref b) => a.has_time() || b.has_time(),
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Expr::Tag(ref key, op, ref value) => write!(f, "{} {} {}", quote_ident(key), op, value),
+            Expr::Time(op, bound) => write!(f, "time {} {}", op, bound),
+            Expr::And(ref a, ref b) => write!(f, "({} AND {})", a, b),
+            Expr::Or(ref a, ref b) => write!(f, "({} OR {})", a, b),
+        }
+    }
+}
+
+/// Value of empty `GROUP BY time()` intervals
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fill {
+    /// `null`, the default
+    Null,
+    /// no row at all
+    None,
+    /// the previous value
+    Previous,
+    /// linear interpolation
+    Linear,
+    /// a constant
+    Value(f64),
+}
+
+impl fmt::Display for Fill {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Fill::Null => write!(f, "fill(null)"),
+            Fill::None => write!(f, "fill(none)"),
+            Fill::Previous => write!(f, "fill(previous)"),
+            Fill::Linear => write!(f, "fill(linear)"),
+            Fill::Value(v) => write!(f, "fill({})", v),
+        }
+    }
+}
+
+/// `SELECT` statement builder, formatted with `Display` or `build`.
+///
+/// ```
+/// use influx_db_client::influxql::{Aggregate, Condition, Field, Fill, Measurement, SelectQuery, Time};
+/// use std::time::Duration;
+///
+/// let query = SelectQuery::new(Measurement::new("cpu").set_database("telegraf"))
+///     .add_field(Field::aggregate(Aggregate::Mean, "usage").set_alias("usage"))
+///     .set_where(
+///         Condition::tag_eq("host", "server01")
+///             .and(Condition::time_gt(Time::Ago(Duration::from_secs(3600)))),
+///     )
+///     .set_group_by_time(Duration::from_secs(600), None)
+///     .add_group_by_tag("region")
+///     .set_fill(Fill::None)
+///     .set_limit(10)
+///     .build();
+///
+/// assert_eq!(
+///     query,
+///     "SELECT mean(\"usage\") AS \"usage\" FROM \"telegraf\"..\"cpu\" \
+///      WHERE (\"host\" = 'server01' AND time > now() - 1h) \
+///      GROUP BY time(10m), \"region\" fill(none) LIMIT 10"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectQuery {
+    fields: Vec<Field>,
+    into: Option<Measurement>,
+    from: Vec<Measurement>,
+    condition: Option<Condition>,
+    group_by_time: Option<(Duration, Option<Duration>)>,
+    group_by_tags: Vec<String>,
+    fill: Option<Fill>,
+    order_desc: bool,
+    limit: Option<u64>,
+    offset: Option<u64>,
+    slimit: Option<u64>,
+    soffset: Option<u64>,
+    tz: Option<String>,
+}
+
+impl SelectQuery {
+    /// Select from a measurement, all fields unless some are added
+    pub fn new<T: Into<Measurement>>(from: T) -> Self {
+        SelectQuery {
+            fields: Vec::new(),
+            into: None,
+            from: vec![from.into()],
+            condition: None,
+            group_by_time: None,
+            group_by_tags: Vec::new(),
+            fill: None,
+            order_desc: false,
+            limit: None,
+            offset: None,
+            slimit: None,
+            soffset: None,
+            tz: None,
+        }
+    }
+
+    /// Select from one more measurement
+    pub fn add_from<T: Into<Measurement>>(mut self, from: T) -> Self {
+        self.from.push(from.into());
+        self
+    }
+
+    /// Add a selected field
+    pub fn add_field<T: Into<Field>>(mut self, field: T) -> Self {
+        self.fields.push(field.into());
+        self
+    }
+
+    /// Write the results into a measurement
+    pub fn set_into<T: Into<Measurement>>(mut self, into: T) -> Self {
+        self.into = Some(into.into());
+        self
+    }
+
+    /// Set the `WHERE` condition, combine predicates with `Condition::and` and `Condition::or`
+    pub fn set_where(mut self, condition: Condition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    /// Group into time intervals, shifted by an optional offset
+    pub fn set_group_by_time(mut self, interval: Duration, offset: Option<Duration>) -> Self {
+        self.group_by_time = Some((interval, offset));
+        self
+    }
+
+    /// Group by a tag key, `*` for all tags
+    pub fn add_group_by_tag<T: Into<String>>(mut self, key: T) -> Self {
+        self.group_by_tags.push(key.into());
+        self
+    }
+
+    /// Set the value of empty time intervals
+    pub fn set_fill(mut self, fill: Fill) -> Self {
+        self.fill = Some(fill);
+        self
+    }
+
+    /// Return the newest points first
+    pub fn set_order_desc(mut self, desc: bool) -> Self {
+        self.order_desc = desc;
+        self
+    }
+
+    /// Return at most this many points per series
+    pub fn set_limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Skip this many points per series
+    pub fn set_offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Return at most this many series
+    pub fn set_slimit(mut self, slimit: u64) -> Self {
+        self.slimit = Some(slimit);
+        self
+    }
+
+    /// Skip this many series
+    pub fn set_soffset(mut self, soffset: u64) -> Self {
+        self.soffset = Some(soffset);
+        self
+    }
+
+    /// Return times in a time zone, such as `America/Chicago`
+    pub fn set_tz<T: Into<String>>(mut self, tz: T) -> Self {
+        self.tz = Some(tz.into());
+        self
+    }
+
+    /// The query text
+    pub fn build(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for SelectQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SELECT ")?;
+        if self.fields.is_empty() {
+            write!(f, "*")?;
+        }
+        write_list(f, &self.fields)?;
+
+        if let Some(ref into) = self.into {
+            write!(f, " INTO {}", into)?;
+        }
+
+        write!(f, " FROM ")?;
+        write_list(f, &self.from)?;
+
+        if let Some(ref condition) = self.condition {
+            write!(f, " WHERE {}", condition)?;
+        }
+
+        if self.group_by_time.is_some() || !self.group_by_tags.is_empty() {
+            write!(f, " GROUP BY ")?;
+            let mut groups = Vec::new();
+            if let Some((interval, offset)) = self.group_by_time {
+                groups.push(match offset {
+                    Some(offset) => format!(
+                        "time({}, {})",
+                        format_duration(interval),
+                        format_duration(offset)
+                    ),
+                    None => format!("time({})", format_duration(interval)),
+                });
+            }
+            groups.extend(self.group_by_tags.iter().map(|key| quote_key(key)));
+            write_list(f, &groups)?;
+        }
+
+        if let Some(fill) = self.fill {
+            write!(f, " {}", fill)?;
+        }
+        if self.order_desc {
+            write!(f, " ORDER BY time DESC")?;
+        }
+        if let Some(limit) = self.limit {
+            write!(f, " LIMIT {}", limit)?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " OFFSET {}", offset)?;
+        }
+        if let Some(slimit) = self.slimit {
+            write!(f, " SLIMIT {}", slimit)?;
+        }
+        if let Some(soffset) = self.soffset {
+            write!(f, " SOFFSET {}", soffset)?;
+        }
+        if let Some(ref tz) = self.tz {
+            write!(f, " tz({})", quote_literal(tz))?;
+        }
+        Ok(())
+    }
+}
+
+fn write_list<T: fmt::Display>(f: &mut fmt::Formatter, items: &[T]) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", item)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn measurement_test() {
+        assert_eq!(Measurement::new("cpu").to_string(), "\"cpu\"");
+        assert_eq!(
+            Measurement::new("cpu")
+                .set_database("db")
+                .set_retention_policy("rp")
+                .to_string(),
+            "\"db\".\"rp\".\"cpu\""
+        );
+        assert_eq!(
+            Measurement::new("cpu").set_database("db").to_string(),
+            "\"db\"..\"cpu\""
+        );
+        assert_eq!(
+            Measurement::new("cpu")
+                .set_retention_policy("rp")
+                .to_string(),
+            "\"rp\".\"cpu\""
+        );
+        assert_eq!(Measurement::regex("^c/p").to_string(), "/^c\\/p/");
+        assert_eq!(
+            Condition::tag_matches("host", "a\\").to_string(),
+            "\"host\" =~ /a\\\\/"
+        );
+        assert_eq!(
+            Measurement::backreference()
+                .set_database("db")
+                .set_retention_policy("rp")
+                .to_string(),
+            "\"db\".\"rp\".:MEASUREMENT"
+        );
+    }
+
+    #[test]
+    fn select_test() {
+        assert_eq!(SelectQuery::new("cpu").build(), "SELECT * FROM \"cpu\"");
+
+        let query = SelectQuery::new("cpu")
+            .add_field("usage")
+            .add_field(Field::aggregate(Aggregate::Count, "*"))
+            .add_field(Field::aggregate(Aggregate::Percentile(95.0), "usage").set_alias("p95"))
+            .set_into(
+                Measurement::backreference()
+                    .set_database("db")
+                    .set_retention_policy("rp"),
+            )
+            .set_where(
+                Condition::tag_eq("host", "it's")
+                    .or(Condition::tag_matches("host", "^web"))
+                    .and(Condition::time_ge(Timestamp::Nanoseconds(1000)))
+                    .and(Condition::time_lt(Time::Now)),
+            )
+            .set_group_by_time(Duration::from_secs(3600), Some(Duration::from_secs(900)))
+            .add_group_by_tag("*")
+            .set_fill(Fill::Value(0.5))
+            .set_order_desc(true)
+            .set_limit(10)
+            .set_offset(20)
+            .set_slimit(1)
+            .set_soffset(2)
+            .set_tz("America/Chicago");
+
+        assert_eq!(
+            query.to_string(),
+            "SELECT \"usage\", count(*), percentile(\"usage\", 95) AS \"p95\" \
+             INTO \"db\".\"rp\".:MEASUREMENT FROM \"cpu\" \
+             WHERE (((\"host\" = 'it\\'s' OR \"host\" =~ /^web/) AND time >= 1000) AND time < now()) \
+             GROUP BY time(1h, 15m), * fill(0.5) ORDER BY time DESC \
+             LIMIT 10 OFFSET 20 SLIMIT 1 SOFFSET 2 tz('America/Chicago')"
+        );
+    }
+
+    #[test]
+    fn duration_test() {
+        let parse = |s: &str| s.parse::<InfluxDuration>();
+        assert_eq!(
+            parse("168h0m0s").unwrap(),
+            InfluxDuration::Finite(Duration::from_secs(7 * 86400))
+        );
+        assert_eq!(
+            parse("1w2d3h4m5s6ms7u8ns").unwrap().to_string(),
+            "1w2d3h4m5s6ms7u8ns"
+        );
+        assert_eq!(
+            parse("10\u{b5}s").unwrap(),
+            InfluxDuration::Finite(Duration::from_micros(10))
+        );
+        assert_eq!(parse("INF").unwrap().to_string(), "INF");
+        assert_eq!(parse("0s").unwrap().to_string(), "0s");
+        for invalid in &[
+            "",
+            "h",
+            "1",
+            "1h30",
+            "1.5h",
+            "-1h",
+            "1 h",
+            "99999999999999999999w",
+        ] {
+            assert!(
+                matches!(parse(invalid), Err(Error::InvalidArgument(_))),
+                "{}",
+                invalid
+            );
+        }
+
+        let json = serde_json::to_string(&InfluxDuration::Finite(Duration::from_secs(60))).unwrap();
+        assert_eq!(json, "\"1m\"");
+        assert_eq!(
+            serde_json::from_str::<InfluxDuration>(&json).unwrap(),
+            InfluxDuration::Finite(Duration::from_secs(60))
+        );
+    }
+
+    #[test]
+    fn condition_test() {
+        assert!(!Condition::tag_ne("a", "b").has_time());
+        assert!(Condition::tag_not_matches("a", "b")
+            .and(Condition::time_le(Time::FromNow(Duration::from_secs(60))))
+            .has_time());
+        assert_eq!(
+            Condition::time_gt(Time::Ago(Duration::from_millis(1500))).to_string(),
+            "time > now() - 1s500ms"
+        );
+    }
+}
@@ -1,9 +1,18 @@
-use serde::{Deserialize, Serialize};
+use crate::error::{Error, RequestKind};
+use crate::{rows, serialization};
+use serde::{
+    de::{DeserializeOwned, Deserializer, Visitor},
+    ser::{self, SerializeMap, Serializer},
+    Deserialize, Serialize,
+};
 use std::{
     borrow::Cow,
     collections::HashMap,
+    convert::TryFrom,
+    fmt,
     iter::{FromIterator, Iterator},
     slice::Iter,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 /// Influxdb value, Please look at [this address](https://docs.influxdata.com/influxdb/v1.3/write_protocols/line_protocol_reference/)
@@ -14,6 +23,8 @@ pub enum Value<'a> {
     String(Cow<'a, str>),
     /// Integer
     Integer(i64),
+    /// Unsigned integer, requires InfluxDB 1.8+ or 2.x
+    UInteger(u64),
     /// float
     Float(f64),
     /// Bool
@@ -30,7 +41,7 @@ pub struct Point<'a> {
     /// fields
     pub fields: HashMap<String, Value<'a>>,
     /// timestamp
-    pub timestamp: Option<i64>,
+    pub timestamp: Option<Timestamp>,
 }
 
 impl<'a> Point<'a> {
@@ -56,11 +67,153 @@ impl<'a> Point<'a> {
         self
     }
 
-    /// Set the specified timestamp
-    pub fn add_timestamp(mut self, timestamp: i64) -> Self {
-        self.timestamp = Some(timestamp);
+    /// Set the specified timestamp.
+    ///
+    /// A raw `i64` is written as is and must match the precision of the write. A `SystemTime`,
+    /// `chrono::DateTime` (feature `chrono`) or `time::OffsetDateTime` (feature `time`) is
+    /// converted to the precision of the write when the point is serialized.
+    pub fn add_timestamp<T: Into<Timestamp>>(mut self, timestamp: T) -> Self {
+        self.timestamp = Some(timestamp.into());
         self
     }
+
+    /// Check that the point can be written as valid line protocol.
+    ///
+    /// Rejects points without fields, empty measurement or keys, `time` as a tag or field key,
+    /// newlines outside of string field values and NaN or infinite floats.
+    pub fn validate(&self) -> Result<(), Error> {
+        self.validate_at(0)
+    }
+
+    pub(crate) fn validate_at(&self, index: usize) -> Result<(), Error> {
+        let invalid = |key: &str, reason: &str| {
+            Err(Error::InvalidPoint {
+                index,
+                key: key.to_string(),
+                reason: reason.to_string(),
+            })
+        };
+
+        if self.measurement.is_empty() {
+            return invalid("", "empty measurement");
+        }
+        if self.measurement.contains('\n') {
+            return invalid(&self.measurement, "newline in measurement");
+        }
+        if self.fields.is_empty() {
+            return invalid(&self.measurement, "point has no fields");
+        }
+
+        for (key, value) in &self.tags {
+            if key.is_empty() {
+                return invalid(key, "empty tag key");
+            }
+            if key == "time" {
+                return invalid(key, "`time` is not allowed as a tag key");
+            }
+            if key.contains('\n') {
+                return invalid(key, "newline in tag key");
+            }
+            match value {
+                Value::String(s) if s.is_empty() => return invalid(key, "empty tag value"),
+                Value::String(s) if s.contains('\n') => {
+                    return invalid(key, "newline in tag value")
+                }
+                _ => (),
+            }
+        }
+
+        for (key, value) in &self.fields {
+            if key.is_empty() {
+                return invalid(key, "empty field key");
+            }
+            if key == "time" {
+                return invalid(key, "`time` is not allowed as a field key");
+            }
+            if key.contains('\n') {
+                return invalid(key, "newline in field key");
+            }
+            if let Value::Float(f) = value {
+                if !f.is_finite() {
+                    return invalid(key, "NaN or infinite float");
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Point timestamp
+///
+/// A `Raw` timestamp is serialized as a bare integer, as `Point::timestamp` used to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timestamp {
+    /// A raw integer, already in the precision of the write
+    Raw(i64),
+    /// An absolute time in nanoseconds since the unix epoch, converted to the precision of the write
+    Nanoseconds(i64),
+}
+
+impl Timestamp {
+    /// The value to write for the given precision
+    pub fn to_precision(&self, precision: Precision) -> i64 {
+        match *self {
+            Timestamp::Raw(t) => t,
+            Timestamp::Nanoseconds(t) => precision.from_nanos(t),
+        }
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Timestamp::Raw(t) => serializer.serialize_i64(t),
+            Timestamp::Nanoseconds(t) => {
+                serializer.serialize_newtype_variant("Timestamp", 1, "Nanoseconds", &t)
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        enum Tagged {
+            Raw(i64),
+            Nanoseconds(i64),
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Raw(i64),
+            Tagged(Tagged),
+        }
+
+        struct TimestampVisitor;
+
+        impl<'de> Visitor<'de> for TimestampVisitor {
+            type Value = Timestamp;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a timestamp")
+            }
+
+            fn visit_newtype_struct<D: Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<Timestamp, D::Error> {
+                Ok(match Repr::deserialize(deserializer)? {
+                    Repr::Raw(t) | Repr::Tagged(Tagged::Raw(t)) => Timestamp::Raw(t),
+                    Repr::Tagged(Tagged::Nanoseconds(t)) => Timestamp::Nanoseconds(t),
+                })
+            }
+        }
+
+        // Asked for by name, so that the time column of query rows decodes with its epoch
+        deserializer.deserialize_newtype_struct("Timestamp", TimestampVisitor)
+    }
 }
 
 /// Points
@@ -126,8 +279,40 @@ pub struct Query {
     pub error: Option<String>,
 }
 
-/// Chunked Query data
-pub type ChunkedQuery<'de, T> = serde_json::StreamDeserializer<'de, T, Query>;
+impl Query {
+    /// Split the response into the result of each statement.
+    ///
+    /// A statement that failed, such as one on a database that does not exist, gives an
+    /// error classified like the error responses of the server. When the whole query
+    /// failed, the only result is that error.
+    ///
+    /// ```
+    /// use influx_db_client::{Error, Query};
+    ///
+    /// let query: Query = serde_json::from_str(
+    ///     r#"{"results":[{"statement_id":0},{"statement_id":1,"error":"database not found: foo"}]}"#,
+    /// )
+    /// .unwrap();
+    /// let statements = query.into_statements();
+    ///
+    /// assert!(statements[0].is_ok());
+    /// assert!(matches!(statements[1], Err(Error::DataBaseDoesNotExist { .. })));
+    /// ```
+    pub fn into_statements(self) -> Vec<Result<Node, Error>> {
+        if let Some(error) = self.error {
+            return vec![Err(Error::from_response(200, &error, RequestKind::Query))];
+        }
+
+        self.results
+            .unwrap_or_default()
+            .into_iter()
+            .map(|node| match node.error {
+                Some(ref error) => Err(Error::from_response(200, error, RequestKind::Query)),
+                None => Ok(node),
+            })
+            .collect()
+    }
+}
 
 /// Query data node
 #[derive(Debug, Clone, Deserialize, PartialEq, Serialize)]
@@ -136,6 +321,22 @@ pub struct Node {
     pub statement_id: Option<u64>,
     /// series
     pub series: Option<Vec<Series>>,
+    /// fail message of this statement
+    pub error: Option<String>,
+    /// more results of this statement follow, in chunked responses
+    #[serde(default)]
+    pub partial: bool,
+    /// informational messages, such as deprecation warnings
+    pub messages: Option<Vec<Message>>,
+}
+
+/// Informational message attached to a statement result
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq, Serialize)]
+pub struct Message {
+    /// severity, such as `warning`
+    pub level: String,
+    /// message text
+    pub text: String,
 }
 
 /// Query data series
@@ -149,6 +350,74 @@ pub struct Series {
     pub columns: Vec<String>,
     /// values
     pub values: Option<Vec<Vec<serde_json::Value>>>,
+    /// more values of this series follow, in chunked responses
+    #[serde(default)]
+    pub partial: bool,
+}
+
+impl Series {
+    /// Deserialize every row into `T`, matching struct fields to column names.
+    ///
+    /// Group by tags are added to each row as string values. The `time` column is an
+    /// RFC3339 string, as the server returns it for queries sent without an `epoch`,
+    /// see `into_rows_with_epoch` for the others. It decodes into `String`,
+    /// `std::time::SystemTime`, `Timestamp::Nanoseconds` or any type that deserializes
+    /// from an RFC3339 string, such as `chrono::DateTime<Utc>` with the `chrono` feature.
+    ///
+    /// ```
+    /// use influx_db_client::Series;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Cpu {
+    ///     time: String,
+    ///     host: String,
+    ///     usage: f64,
+    /// }
+    ///
+    /// let series: Series = serde_json::from_str(
+    ///     r#"{"name":"cpu","tags":{"host":"a"},"columns":["time","usage"],"values":[["1970-01-01T00:00:10Z",0.5]]}"#,
+    /// )
+    /// .unwrap();
+    /// let rows: Vec<Cpu> = series.into_rows().unwrap();
+    ///
+    /// assert_eq!(
+    ///     (rows[0].time.as_str(), rows[0].host.as_str(), rows[0].usage),
+    ///     ("1970-01-01T00:00:10Z", "a", 0.5)
+    /// );
+    /// ```
+    pub fn into_rows<T: DeserializeOwned>(self) -> Result<Vec<T>, Error> {
+        self.into_rows_with_epoch(None)
+    }
+
+    /// Deserialize every row into `T` like `into_rows`, for a query sent with `epoch`.
+    ///
+    /// With an epoch the `time` column is an integer in that precision. It decodes into
+    /// integers as it is, and into `std::time::SystemTime`, `Timestamp::Nanoseconds`
+    /// or `String` converted to the time it stands for, the latter as an RFC3339 string
+    /// in UTC, so that `chrono::DateTime<Utc>` decodes too.
+    pub fn into_rows_with_epoch<T: DeserializeOwned>(
+        self,
+        epoch: Option<Precision>,
+    ) -> Result<Vec<T>, Error> {
+        let Series {
+            tags,
+            columns,
+            values,
+            ..
+        } = self;
+        let tags = tags.unwrap_or_default();
+
+        values
+            .unwrap_or_default()
+            .into_iter()
+            .map(|row| {
+                let mut object = tags.clone();
+                object.extend(columns.iter().cloned().zip(row));
+                Ok(rows::from_row(object, epoch)?)
+            })
+            .collect()
+    }
 }
 
 /// Time accuracy
@@ -180,6 +449,28 @@ impl Precision {
             Precision::Hours => "h",
         }
     }
+
+    /// Number of nanoseconds in one unit of this precision
+    pub fn nanos_per_unit(&self) -> i64 {
+        match *self {
+            Precision::Nanoseconds => 1,
+            Precision::Microseconds => 1_000,
+            Precision::Milliseconds => 1_000_000,
+            Precision::Seconds => 1_000_000_000,
+            Precision::Minutes => 60_000_000_000,
+            Precision::Hours => 3_600_000_000_000,
+        }
+    }
+
+    /// Convert a value in this precision to nanoseconds, `None` if it overflows
+    pub fn to_nanos(&self, value: i64) -> Option<i64> {
+        value.checked_mul(self.nanos_per_unit())
+    }
+
+    /// Convert nanoseconds to this precision, rounding down
+    pub fn from_nanos(&self, nanos: i64) -> i64 {
+        nanos.div_euclid(self.nanos_per_unit())
+    }
 }
 
 /// Create Points by macro
@@ -213,11 +504,56 @@ macro_rules! point {
             measurement: String::from($x),
             tags: $y,
             fields: $z,
-            timestamp: Some($a),
+            timestamp: Some($a.into()),
         }
     }};
 }
 
+impl From<i64> for Timestamp {
+    fn from(v: i64) -> Self {
+        Timestamp::Raw(v)
+    }
+}
+
+/// Times outside of the `i64` nanosecond range (years 1677 to 2262) saturate
+impl From<SystemTime> for Timestamp {
+    fn from(v: SystemTime) -> Self {
+        let nanos = match v.duration_since(UNIX_EPOCH) {
+            Ok(d) => i64::try_from(d.as_nanos()).unwrap_or(i64::MAX),
+            Err(e) => i64::try_from(e.duration().as_nanos())
+                .map(|n| -n)
+                .unwrap_or(i64::MIN),
+        };
+        Timestamp::Nanoseconds(nanos)
+    }
+}
+
+/// Times outside of the `i64` nanosecond range (years 1677 to 2262) saturate
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> From<chrono::DateTime<Tz>> for Timestamp {
+    fn from(v: chrono::DateTime<Tz>) -> Self {
+        let nanos = v.timestamp_nanos_opt().unwrap_or(if v.timestamp() < 0 {
+            i64::MIN
+        } else {
+            i64::MAX
+        });
+        Timestamp::Nanoseconds(nanos)
+    }
+}
+
+/// Times outside of the `i64` nanosecond range (years 1677 to 2262) saturate
+#[cfg(feature = "time")]
+impl From<time::OffsetDateTime> for Timestamp {
+    fn from(v: time::OffsetDateTime) -> Self {
+        let nanos = v.unix_timestamp_nanos();
+        Timestamp::Nanoseconds(i64::try_from(nanos).unwrap_or(if nanos < 0 {
+            i64::MIN
+        } else {
+            i64::MAX
+        }))
+    }
+}
+
 impl<'a> From<String> for Value<'a> {
     fn from(v: String) -> Self {
         Self::String(Cow::Owned(v))
@@ -254,6 +590,24 @@ impl<'a> From<i8> for Value<'a> {
     }
 }
 
+impl<'a> From<u64> for Value<'a> {
+    fn from(v: u64) -> Self {
+        Self::UInteger(v)
+    }
+}
+
+impl<'a> From<u32> for Value<'a> {
+    fn from(v: u32) -> Self {
+        Self::UInteger(v.into())
+    }
+}
+
+impl<'a> From<usize> for Value<'a> {
+    fn from(v: usize) -> Self {
+        Self::UInteger(v as u64)
+    }
+}
+
 impl<'a> From<f64> for Value<'a> {
     fn from(v: f64) -> Self {
         Self::Float(v)
@@ -271,3 +625,457 @@ impl<'a> From<bool> for Value<'a> {
         Self::Boolean(v)
     }
 }
+
+/// A value bound to a `$name` placeholder, see `Client::query_with_params`
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryParam<'a> {
+    /// string
+    String(Cow<'a, str>),
+    /// Integer
+    Integer(i64),
+    /// float, must be finite
+    Float(f64),
+    /// Bool
+    Boolean(bool),
+    /// duration literal, such as `1h30m`
+    Duration(Duration),
+    /// point in time, sent as nanoseconds since the unix epoch.
+    /// A `Timestamp::Raw` must already be in nanoseconds
+    Time(Timestamp),
+}
+
+impl<'a> Serialize for QueryParam<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            QueryParam::String(ref s) => serializer.serialize_str(s),
+            QueryParam::Integer(i) => serializer.serialize_i64(i),
+            QueryParam::Float(f) if !f.is_finite() => Err(ser::Error::custom(
+                "NaN or infinite float can't be bound to a query",
+            )),
+            QueryParam::Float(f) => serializer.serialize_f64(f),
+            QueryParam::Boolean(b) => serializer.serialize_bool(b),
+            QueryParam::Duration(d) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("duration", &serialization::format_duration(d))?;
+                map.end()
+            }
+            QueryParam::Time(t) => serializer.serialize_i64(t.to_precision(Precision::Nanoseconds)),
+        }
+    }
+}
+
+impl<'a> From<String> for QueryParam<'a> {
+    fn from(v: String) -> Self {
+        Self::String(Cow::Owned(v))
+    }
+}
+
+impl<'a> From<&'a str> for QueryParam<'a> {
+    fn from(v: &'a str) -> Self {
+        Self::String(Cow::Borrowed(v))
+    }
+}
+
+impl<'a> From<i64> for QueryParam<'a> {
+    fn from(v: i64) -> Self {
+        Self::Integer(v)
+    }
+}
+
+impl<'a> From<i32> for QueryParam<'a> {
+    fn from(v: i32) -> Self {
+        Self::Integer(v.into())
+    }
+}
+
+impl<'a> From<f64> for QueryParam<'a> {
+    fn from(v: f64) -> Self {
+        Self::Float(v)
+    }
+}
+
+impl<'a> From<bool> for QueryParam<'a> {
+    fn from(v: bool) -> Self {
+        Self::Boolean(v)
+    }
+}
+
+impl<'a> From<Duration> for QueryParam<'a> {
+    fn from(v: Duration) -> Self {
+        Self::Duration(v)
+    }
+}
+
+impl<'a> From<Timestamp> for QueryParam<'a> {
+    fn from(v: Timestamp) -> Self {
+        Self::Time(v)
+    }
+}
+
+impl<'a> From<SystemTime> for QueryParam<'a> {
+    fn from(v: SystemTime) -> Self {
+        Self::Time(v.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Row {
+        time: String,
+        host: String,
+        region: Option<String>,
+        value: Option<f64>,
+    }
+
+    #[test]
+    fn series_into_rows_test() {
+        let series: Series = serde_json::from_str(
+            r#"{
+                "name": "cpu",
+                "tags": {"host": "a"},
+                "columns": ["time", "value", "region"],
+                "values": [
+                    ["2020-01-01T00:00:00Z", 0.5, "us"],
+                    ["2020-01-01T00:01:00Z", null, null]
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            series.into_rows::<Row>().unwrap(),
+            vec![
+                Row {
+                    time: "2020-01-01T00:00:00Z".to_string(),
+                    host: "a".to_string(),
+                    region: Some("us".to_string()),
+                    value: Some(0.5),
+                },
+                Row {
+                    time: "2020-01-01T00:01:00Z".to_string(),
+                    host: "a".to_string(),
+                    region: None,
+                    value: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn series_into_time_rows_test() {
+        use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Row {
+            time: SystemTime,
+            text: Option<String>,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Text {
+            time: String,
+        }
+
+        let series = |time: &str| -> Series {
+            serde_json::from_str(&format!(
+                r#"{{"columns": ["time", "text"], "values": [[{}, null]]}}"#,
+                time
+            ))
+            .unwrap()
+        };
+        let expected = vec![Row {
+            time: UNIX_EPOCH + Duration::from_millis(1500),
+            text: None,
+        }];
+
+        assert_eq!(
+            series("1500")
+                .into_rows_with_epoch::<Row>(Some(Precision::Milliseconds))
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            series(r#""1970-01-01T00:00:01.5Z""#)
+                .into_rows::<Row>()
+                .unwrap(),
+            expected
+        );
+        assert!(matches!(
+            series("1500").into_rows::<Row>(),
+            Err(Error::DeserializeError(_))
+        ));
+        assert_eq!(
+            series("1500")
+                .into_rows_with_epoch::<Text>(Some(Precision::Milliseconds))
+                .unwrap(),
+            vec![Text {
+                time: "1970-01-01T00:00:01.5Z".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn series_into_rows_error_test() {
+        let series: Series =
+            serde_json::from_str(r#"{"columns": ["time"], "values": [[1]]}"#).unwrap();
+
+        assert!(matches!(
+            series.into_rows::<Row>(),
+            Err(Error::DeserializeError(_))
+        ));
+    }
+
+    #[test]
+    fn query_into_statements_test() {
+        let query: Query = serde_json::from_str(
+            r#"{"results":[
+                {"statement_id":0,"series":[{"name":"cpu","columns":["time","value"],"values":[[1,2]],"partial":true}],"partial":true},
+                {"statement_id":1,"error":"retention policy not found: short"},
+                {"statement_id":2,"messages":[{"level":"warning","text":"deprecated"}]}
+            ]}"#,
+        )
+        .unwrap();
+        let nodes = query.clone().results.unwrap();
+        assert!(nodes[0].partial);
+        assert!(nodes[0].series.as_ref().unwrap()[0].partial);
+        assert!(!nodes[2].partial);
+        assert_eq!(
+            nodes[2].messages,
+            Some(vec![Message {
+                level: "warning".to_string(),
+                text: "deprecated".to_string()
+            }])
+        );
+
+        let statements = query.into_statements();
+        assert_eq!(statements.len(), 3);
+        assert_eq!(statements[0].as_ref().unwrap().statement_id, Some(0));
+        assert!(matches!(
+            statements[1],
+            Err(Error::RetentionPolicyDoesNotExist { ref message, .. }) if message == "retention policy not found: short"
+        ));
+        assert!(statements[2].is_ok());
+
+        let query: Query = serde_json::from_str(r#"{"error":"error parsing query"}"#).unwrap();
+        assert!(matches!(
+            &query.into_statements()[..],
+            [Err(Error::Server { status: 200, .. })]
+        ));
+    }
+
+    #[test]
+    fn query_param_test() {
+        let params: std::collections::BTreeMap<_, _> = vec![
+            ("b", QueryParam::from(true)),
+            ("d", QueryParam::from(Duration::from_secs(5400))),
+            ("f", QueryParam::from(0.5)),
+            ("i", QueryParam::from(-3)),
+            ("s", QueryParam::from("it's \"quoted\"")),
+            ("t", QueryParam::from(UNIX_EPOCH + Duration::from_secs(1))),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            serde_json::to_string(&params).unwrap(),
+            r#"{"b":true,"d":{"duration":"1h30m"},"f":0.5,"i":-3,"s":"it's \"quoted\"","t":1000000000}"#
+        );
+        assert!(serde_json::to_string(&QueryParam::from(f64::NAN)).is_err());
+    }
+
+    fn invalid_key(point: Point) -> String {
+        match point.validate() {
+            Err(Error::InvalidPoint { key, .. }) => key,
+            other => panic!("expected InvalidPoint, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn validate_point_test() {
+        assert!(Point::new("m").add_field("f", 1).validate().is_ok());
+        assert_eq!(invalid_key(Point::new("m")), "m");
+        assert_eq!(invalid_key(Point::new("").add_field("f", 1)), "");
+        assert_eq!(invalid_key(Point::new("m").add_field("f", f64::NAN)), "f");
+        assert_eq!(
+            invalid_key(Point::new("m").add_field("f", f64::INFINITY)),
+            "f"
+        );
+        assert_eq!(invalid_key(Point::new("m").add_field("time", 1)), "time");
+        assert_eq!(
+            invalid_key(Point::new("m").add_tag("time", "x").add_field("f", 1)),
+            "time"
+        );
+        assert_eq!(
+            invalid_key(Point::new("m").add_tag("", "x").add_field("f", 1)),
+            ""
+        );
+        assert_eq!(
+            invalid_key(Point::new("m").add_tag("host", "a\nb").add_field("f", 1)),
+            "host"
+        );
+        assert!(Point::new("m").add_field("f", "a\nb").validate().is_ok());
+    }
+
+    #[test]
+    fn precision_nanos_test() {
+        assert_eq!(Precision::Seconds.to_nanos(2), Some(2_000_000_000));
+        assert_eq!(Precision::Hours.to_nanos(i64::MAX), None);
+        assert_eq!(Precision::Milliseconds.from_nanos(1_999_999), 1);
+        assert_eq!(Precision::Seconds.from_nanos(-1), -1);
+        assert_eq!(Precision::Nanoseconds.from_nanos(42), 42);
+    }
+
+    #[test]
+    fn timestamp_test() {
+        use std::time::Duration;
+
+        let time = UNIX_EPOCH + Duration::new(1_600_000_000, 123_456_789);
+        let timestamp = Timestamp::from(time);
+
+        assert_eq!(timestamp, Timestamp::Nanoseconds(1_600_000_000_123_456_789));
+        assert_eq!(
+            timestamp.to_precision(Precision::Milliseconds),
+            1_600_000_000_123
+        );
+        assert_eq!(timestamp.to_precision(Precision::Hours), 444_444);
+        assert_eq!(Timestamp::from(42).to_precision(Precision::Hours), 42);
+        assert_eq!(
+            Timestamp::from(UNIX_EPOCH - Duration::from_secs(1)),
+            Timestamp::Nanoseconds(-1_000_000_000)
+        );
+    }
+
+    #[test]
+    fn timestamp_serde_test() {
+        let point: Point =
+            serde_json::from_str(r#"{"measurement":"m","tags":{},"fields":{},"timestamp":5}"#)
+                .unwrap();
+        assert_eq!(point.timestamp, Some(Timestamp::Raw(5)));
+        assert_eq!(
+            serde_json::to_string(&point).unwrap(),
+            r#"{"measurement":"m","tags":{},"fields":{},"timestamp":5}"#
+        );
+
+        let timestamp = Timestamp::Nanoseconds(1_600_000_000_123_456_789);
+        let json = serde_json::to_string(&timestamp).unwrap();
+        assert_eq!(json, r#"{"Nanoseconds":1600000000123456789}"#);
+        assert_eq!(serde_json::from_str::<Timestamp>(&json).unwrap(), timestamp);
+        assert_eq!(
+            serde_json::from_str::<Timestamp>(r#"{"Raw":7}"#).unwrap(),
+            Timestamp::Raw(7)
+        );
+    }
+
+    #[test]
+    fn series_into_timestamp_rows_test() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Row {
+            time: Timestamp,
+        }
+
+        let series = |time: &str| -> Series {
+            serde_json::from_str(&format!(
+                r#"{{"columns": ["time"], "values": [[{}]]}}"#,
+                time
+            ))
+            .unwrap()
+        };
+
+        assert_eq!(
+            series("1500")
+                .into_rows_with_epoch::<Row>(Some(Precision::Milliseconds))
+                .unwrap(),
+            vec![Row {
+                time: Timestamp::Nanoseconds(1_500_000_000)
+            }]
+        );
+        assert_eq!(
+            series(r#""1970-01-01T00:00:01.5Z""#)
+                .into_rows::<Row>()
+                .unwrap(),
+            vec![Row {
+                time: Timestamp::Nanoseconds(1_500_000_000)
+            }]
+        );
+        assert_eq!(
+            series("1500").into_rows::<Row>().unwrap(),
+            vec![Row {
+                time: Timestamp::Raw(1500)
+            }]
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_timestamp_test() {
+        use chrono::TimeZone;
+
+        let time = chrono::Utc.timestamp_opt(1_600_000_000, 5).unwrap();
+
+        assert_eq!(
+            Timestamp::from(time),
+            Timestamp::Nanoseconds(1_600_000_000_000_000_005)
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn series_into_chrono_rows_test() {
+        use chrono::{DateTime, TimeZone, Utc};
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Row {
+            time: DateTime<Utc>,
+        }
+
+        let expected = vec![Row {
+            time: Utc.timestamp_opt(1, 500_000_000).unwrap(),
+        }];
+        let series: Series =
+            serde_json::from_str(r#"{"columns": ["time"], "values": [[1500]]}"#).unwrap();
+        assert_eq!(
+            series
+                .into_rows_with_epoch::<Row>(Some(Precision::Milliseconds))
+                .unwrap(),
+            expected
+        );
+        let series: Series = serde_json::from_str(
+            r#"{"columns": ["time"], "values": [["1970-01-01T00:00:01.5Z"]]}"#,
+        )
+        .unwrap();
+        assert_eq!(series.into_rows::<Row>().unwrap(), expected);
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn time_timestamp_test() {
+        let time =
+            time::OffsetDateTime::from_unix_timestamp_nanos(1_600_000_000_000_000_005).unwrap();
+
+        assert_eq!(
+            Timestamp::from(time),
+            Timestamp::Nanoseconds(1_600_000_000_000_000_005)
+        );
+    }
+
+    #[test]
+    fn deserialize_value_test() {
+        let values: Vec<Value> =
+            serde_json::from_str("[-1, 1, 18446744073709551615, 1.5, true, \"s\"]").unwrap();
+
+        assert_eq!(
+            values,
+            vec![
+                Value::Integer(-1),
+                Value::Integer(1),
+                Value::UInteger(u64::MAX),
+                Value::Float(1.5),
+                Value::Boolean(true),
+                Value::String(Cow::Borrowed("s")),
+            ]
+        )
+    }
+}
@@ -29,7 +29,7 @@
 //!
 //! let points = points!(point1, point);
 //!
-//! // if Precision is None, the default is second
+//! // if Precision is None, the client default is used, second unless changed by set_precision
 //! // Multiple write
 //! client.write_points(points, Some(Precision::Seconds), None).unwrap();
 //!
@@ -38,6 +38,39 @@
 //! println!("{:?}", res.unwrap()[0].series)
 //! ```
 //!
+//! ### derive
+//!
+//! With the `derive` feature, structs can be mapped to points by `#[derive(InfluxPoint)]`
+//!
+//! ```Rust
+//! use influx_db_client::{InfluxPoint, Point};
+//!
+//! #[derive(InfluxPoint)]
+//! #[influx(measurement = "cpu")]
+//! struct Cpu {
+//!     #[influx(tag)]
+//!     host: String,
+//!     usage: f64,
+//!     #[influx(timestamp)]
+//!     time: i64,
+//! }
+//!
+//! let cpu = Cpu { host: "a".to_string(), usage: 0.5, time: 1_508_981_970 };
+//! client.write_point(Point::from(cpu), Some(Precision::Seconds), None).await.unwrap();
+//! ```
+//!
+//! ### InfluxDB 2.x
+//!
+//! ```Rust
+//! use influx_db_client::{Point, Precision, V2Client};
+//!
+//! let client = V2Client::new("http://localhost:8086".parse().unwrap(), "my-org", "my-bucket")
+//!     .set_token("my-token");
+//!
+//! let point = Point::new("cpu").add_tag("host", "a").add_field("usage", 0.5);
+//! client.write_point(point, Some(Precision::Milliseconds)).await.unwrap();
+//! ```
+//!
 //! ### udp
 //!
 //! ```Rust
@@ -55,17 +88,41 @@
 #![deny(warnings)]
 #![deny(missing_docs)]
 
+/// Background batching writer
+pub mod batch;
+/// Streaming of chunked query responses
+pub mod chunked;
 /// All API on influxdb client, Including udp, http
 pub mod client;
+/// Line protocol encoder
+pub mod encoder;
 /// Error module
 pub mod error;
+/// Typed InfluxQL statement builders
+pub mod influxql;
 /// Points and Query Data Deserialize
 pub mod keys;
+/// Line protocol parser
+pub mod parser;
+/// Retrying of http requests
+pub mod retry;
+/// Deserialization of query rows into user types
+pub(crate) mod rows;
+/// Typed results of schema queries
+pub mod schema;
 /// Serialization module
 pub(crate) mod serialization;
 
-pub use client::{Client, UdpClient};
-pub use error::Error;
-pub use keys::{ChunkedQuery, Node, Point, Points, Precision, Query, Series, Value};
+pub use batch::{BatchHandle, BatchOptions, BatchWriter};
+pub use chunked::ChunkedQuery;
+pub use client::{Client, Compression, UdpClient, V2Client};
+pub use encoder::LineProtocolEncoder;
+pub use error::{Error, RequestKind};
+pub use keys::{
+    Message, Node, Point, Points, Precision, Query, QueryParam, Series, Timestamp, Value,
+};
 
+#[cfg(feature = "derive")]
+pub use influx_db_client_derive::InfluxPoint;
 pub use reqwest;
+pub use retry::RetryPolicy;
@@ -0,0 +1,418 @@
+use crate::{error::Error, Point, Points, Timestamp, Value};
+use std::{borrow::Cow, collections::HashMap};
+
+/// Parse line protocol text, yielding one result per non-empty line.
+///
+/// Blank lines and `#` comments are skipped. A line that fails to parse yields an
+/// `Error::ParseError` carrying its 1-based line number, and parsing resumes on the next line.
+///
+/// Tag values are always parsed back as `Value::String`, since line protocol does not
+/// keep their type, and timestamps as `Timestamp::Raw` in the precision of the text.
+pub fn parse_lines(input: &str) -> LineParser<'_> {
+    LineParser {
+        input,
+        pos: 0,
+        line: 0,
+    }
+}
+
+/// Parse line protocol text into `Points`, failing on the first invalid line
+pub fn parse_points(input: &str) -> Result<Points<'_>, Error> {
+    parse_lines(input)
+        .collect::<Result<Vec<_>, _>>()
+        .map(Points::create_new)
+}
+
+/// Iterator over the points of a line protocol text, created by `parse_lines`
+#[derive(Debug, Clone)]
+pub struct LineParser<'a> {
+    input: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Iterator for LineParser<'a> {
+    type Item = Result<Point<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.pos >= self.input.len() {
+                return None;
+            }
+            self.line += 1;
+
+            let mut cursor = Cursor {
+                input: self.input,
+                pos: self.pos,
+            };
+            cursor.skip_whitespace();
+
+            match cursor.peek() {
+                None => {
+                    self.pos = cursor.pos;
+                    return None;
+                }
+                Some(b'\n') => {
+                    self.pos = cursor.pos + 1;
+                    continue;
+                }
+                Some(b'#') => {
+                    cursor.skip_line();
+                    self.pos = cursor.pos;
+                    continue;
+                }
+                _ => (),
+            }
+
+            let line = self.line;
+            let result = cursor.point();
+            if result.is_err() {
+                cursor.skip_line();
+            }
+            // A quoted string field may span several physical lines
+            self.line += self.input[self.pos..cursor.pos]
+                .bytes()
+                .filter(|b| *b == b'\n')
+                .count()
+                .saturating_sub(1);
+            self.pos = cursor.pos;
+
+            return Some(result.map_err(|message| Error::ParseError { line, message }));
+        }
+    }
+}
+
+struct Cursor<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    /// Move past the next newline, or to the end of input
+    fn skip_line(&mut self) {
+        match self.input[self.pos..].find('\n') {
+            Some(i) => self.pos += i + 1,
+            None => self.pos = self.input.len(),
+        }
+    }
+
+    /// Consume the end of a line, which must be a newline or the end of input
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Ok(()),
+            Some(b'\n') => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(_) => Err(format!(
+                "unexpected trailing data '{}'",
+                self.rest_of_line()
+            )),
+        }
+    }
+
+    fn rest_of_line(&self) -> &'a str {
+        let rest = &self.input[self.pos..];
+        rest.split('\n').next().unwrap_or(rest).trim_end()
+    }
+
+    /// Read up to an unescaped byte in `stop`, a newline or the end of input.
+    ///
+    /// A backslash followed by a byte in `escapable` yields that byte,
+    /// any other backslash is kept as is.
+    fn escaped(&mut self, stop: &[u8], escapable: &[u8]) -> Cow<'a, str> {
+        let bytes = self.input.as_bytes();
+        let start = self.pos;
+        let mut owned: Option<String> = None;
+        let mut chunk = start;
+
+        while let Some(&b) = bytes.get(self.pos) {
+            if b == b'\\' {
+                if let Some(&next) = bytes.get(self.pos + 1) {
+                    if escapable.contains(&next) {
+                        let buf = owned.get_or_insert_with(String::new);
+                        buf.push_str(&self.input[chunk..self.pos]);
+                        buf.push(next as char);
+                        self.pos += 2;
+                        chunk = self.pos;
+                        continue;
+                    }
+                }
+            } else if b == b'\n' || stop.contains(&b) {
+                break;
+            }
+            self.pos += 1;
+        }
+
+        match owned {
+            Some(mut buf) => {
+                buf.push_str(&self.input[chunk..self.pos]);
+                Cow::Owned(buf)
+            }
+            None => Cow::Borrowed(&self.input[start..self.pos]),
+        }
+    }
+
+    fn expect(&mut self, byte: u8, what: &str) -> Result<(), String> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("expected '{}' after {}", byte as char, what))
+        }
+    }
+
+    fn point(&mut self) -> Result<Point<'a>, String> {
+        let measurement = self.escaped(b", ", b", ");
+        if measurement.is_empty() {
+            return Err("missing measurement".to_string());
+        }
+
+        let mut tags = HashMap::new();
+        while self.peek() == Some(b',') {
+            self.pos += 1;
+            let key = self.escaped(b"=, ", b",= ");
+            if key.is_empty() {
+                return Err("missing tag key".to_string());
+            }
+            self.expect(b'=', &format!("tag key '{}'", key))?;
+            let value = self.escaped(b", ", b",= ");
+            if value.is_empty() {
+                return Err(format!("missing value for tag '{}'", key));
+            }
+            tags.insert(key.into_owned(), Value::String(value));
+        }
+
+        self.skip_whitespace();
+        if matches!(self.peek(), None | Some(b'\n')) {
+            return Err("missing fields".to_string());
+        }
+
+        let mut fields = HashMap::new();
+        loop {
+            let key = self.escaped(b"=, ", b",= ");
+            if key.is_empty() {
+                return Err("missing field key".to_string());
+            }
+            self.expect(b'=', &format!("field key '{}'", key))?;
+            let value = self.field_value(&key)?;
+            fields.insert(key.into_owned(), value);
+
+            if self.peek() == Some(b',') {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+
+        self.skip_whitespace();
+        let timestamp = match self.peek() {
+            None | Some(b'\n') => None,
+            Some(_) => {
+                let raw = self.escaped(b" \t\r", b"");
+                let raw = raw
+                    .parse::<i64>()
+                    .map_err(|_| format!("invalid timestamp '{}'", raw))?;
+                Some(Timestamp::Raw(raw))
+            }
+        };
+
+        self.end_of_line()?;
+
+        Ok(Point {
+            measurement: measurement.into_owned(),
+            tags,
+            fields,
+            timestamp,
+        })
+    }
+
+    fn field_value(&mut self, key: &str) -> Result<Value<'a>, String> {
+        if self.peek() == Some(b'"') {
+            self.pos += 1;
+            let value = self.escaped(b"\"", b"\"\\");
+            // Newlines are allowed inside string fields
+            if self.peek() == Some(b'\n') {
+                let mut value = value.into_owned();
+                while self.peek() == Some(b'\n') {
+                    value.push('\n');
+                    self.pos += 1;
+                    value.push_str(&self.escaped(b"\"", b"\"\\"));
+                }
+                self.expect(b'"', &format!("string value of field '{}'", key))?;
+                return Ok(Value::String(Cow::Owned(value)));
+            }
+            self.expect(b'"', &format!("string value of field '{}'", key))?;
+            return Ok(Value::String(value));
+        }
+
+        let raw = self.escaped(b", \t\r", b"");
+        parse_field_value(&raw).ok_or_else(|| {
+            if raw.is_empty() {
+                format!("missing value for field '{}'", key)
+            } else {
+                format!("invalid value '{}' for field '{}'", raw, key)
+            }
+        })
+    }
+}
+
+/// Parse an unquoted field value: integer, unsigned integer, boolean or float
+fn parse_field_value<'a>(raw: &str) -> Option<Value<'a>> {
+    if let Some(int) = raw.strip_suffix('i') {
+        return int.parse().ok().map(Value::Integer);
+    }
+    if let Some(uint) = raw.strip_suffix('u') {
+        return uint.parse().ok().map(Value::UInteger);
+    }
+
+    match raw {
+        "t" | "T" | "true" | "True" | "TRUE" => return Some(Value::Boolean(true)),
+        "f" | "F" | "false" | "False" | "FALSE" => return Some(Value::Boolean(false)),
+        _ => (),
+    }
+
+    // Reject `inf`, `NaN` and friends, which Rust would happily parse
+    if !raw.starts_with(|c: char| c.is_ascii_digit() || matches!(c, '-' | '+' | '.')) {
+        return None;
+    }
+    raw.parse::<f64>()
+        .ok()
+        .filter(|f| f.is_finite())
+        .map(Value::Float)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LineProtocolEncoder;
+
+    #[test]
+    fn parse_simple_line_test() {
+        let points = parse_points(
+            "cpu,host=a,region=us usage=0.5,count=3i,total=18446744073709551615u,ok=t 1600000000\n",
+        )
+        .unwrap();
+        let point = &points.point[0];
+
+        assert_eq!(point.measurement, "cpu");
+        assert_eq!(point.tags["host"], Value::String(Cow::Borrowed("a")));
+        assert_eq!(point.tags["region"], Value::String(Cow::Borrowed("us")));
+        assert_eq!(point.fields["usage"], Value::Float(0.5));
+        assert_eq!(point.fields["count"], Value::Integer(3));
+        assert_eq!(point.fields["total"], Value::UInteger(u64::MAX));
+        assert_eq!(point.fields["ok"], Value::Boolean(true));
+        assert_eq!(point.timestamp, Some(Timestamp::Raw(1_600_000_000)));
+    }
+
+    #[test]
+    fn parse_escapes_test() {
+        let points = parse_points(
+            "my\\ measure\\,ment,ta\\=g=v\\ a\\,l\\=ue f\\ ield=\"say \\\"hi\\\" \\\\o/\"",
+        )
+        .unwrap();
+        let point = &points.point[0];
+
+        assert_eq!(point.measurement, "my measure,ment");
+        assert_eq!(point.tags["ta=g"], Value::String(Cow::Borrowed("v a,l=ue")));
+        assert_eq!(
+            point.fields["f ield"],
+            Value::String(Cow::Borrowed("say \"hi\" \\o/"))
+        );
+        assert_eq!(point.timestamp, None);
+    }
+
+    #[test]
+    fn parse_borrows_when_unescaped_test() {
+        let points = parse_points("m,t=plain f=\"plain\"").unwrap();
+
+        assert!(matches!(
+            points.point[0].tags["t"],
+            Value::String(Cow::Borrowed(_))
+        ));
+        assert!(matches!(
+            points.point[0].fields["f"],
+            Value::String(Cow::Borrowed(_))
+        ));
+    }
+
+    #[test]
+    fn parse_skips_comments_and_blank_lines_test() {
+        let input = "# header\n\nm f=1i\r\n   \nm f=2i 10\n";
+        let points = parse_points(input).unwrap();
+
+        assert_eq!(points.point.len(), 2);
+        assert_eq!(points.point[1].timestamp, Some(Timestamp::Raw(10)));
+    }
+
+    #[test]
+    fn parse_multiline_string_field_test() {
+        let mut lines = parse_lines("m f=\"a\nb\"\nbroken\nm f=1i");
+
+        assert_eq!(
+            lines.next().unwrap().unwrap().fields["f"],
+            Value::String(Cow::Borrowed("a\nb"))
+        );
+        assert_eq!(
+            lines.next().unwrap(),
+            Err(Error::ParseError {
+                line: 3,
+                message: "missing fields".to_string()
+            })
+        );
+        assert!(lines.next().unwrap().is_ok());
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn parse_errors_are_per_line_test() {
+        let input = "m f=1i\nm f=abc\nm,t f=1\nm f=1 notatime\nm f=nan\nm f=1i\nm f=\"open";
+        let results: Vec<_> = parse_lines(input).collect();
+
+        let errors: Vec<_> = results
+            .iter()
+            .filter_map(|r| match r {
+                Err(Error::ParseError { line, .. }) => Some(*line),
+                _ => None,
+            })
+            .collect();
+
+        assert!(results[0].is_ok());
+        assert!(results[5].is_ok());
+        assert_eq!(errors, vec![2, 3, 4, 5, 7]);
+        assert!(parse_points(input).is_err());
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let point = Point::new("disk usage,total")
+            .add_tag("host name", "server,01")
+            .add_tag("path", "C:\\=data")
+            .add_field("free", 12.5)
+            .add_field("used", -3)
+            .add_field("reads", 42u64)
+            .add_field("readonly", false)
+            .add_field("label", "\\\"quoted\\\" \"label\" \\ back\\\\slash")
+            .add_timestamp(1_508_981_970);
+        let points = Points::new(point);
+
+        let mut line = Vec::new();
+        LineProtocolEncoder::new()
+            .encode(&points, &mut line)
+            .unwrap();
+        let parsed = parse_points(std::str::from_utf8(&line).unwrap()).unwrap();
+
+        assert_eq!(parsed, points);
+    }
+}
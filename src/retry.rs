@@ -0,0 +1,267 @@
+use reqwest::{header::RETRY_AFTER, RequestBuilder, Response};
+use std::{
+    collections::hash_map::RandomState,
+    convert::TryFrom,
+    hash::{BuildHasher, Hasher},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{error, serialization};
+
+/// Retrying of transient request failures.
+///
+/// Connection errors, `429 Too Many Requests`, `503 Service Unavailable` and
+/// "hinted handoff queue full" errors are retried, with an exponential backoff
+/// or the delay of the server's `Retry-After` header, in seconds or as an HTTP date,
+/// up to the max backoff. A `400 Bad Request` is never retried.
+///
+/// Waiting between attempts needs a tokio runtime with the time driver enabled.
+///
+/// ```Rust
+/// use influx_db_client::{Client, RetryPolicy};
+/// use std::time::Duration;
+///
+/// let client = Client::default().set_retry_policy(
+///     RetryPolicy::new(5)
+///         .set_initial_backoff(Duration::from_millis(200))
+///         .set_max_backoff(Duration::from_secs(5)),
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+}
+
+impl RetryPolicy {
+    /// Make up to `max_attempts` attempts, waiting 100ms after the first failure,
+    /// doubling up to 10s, with jitter
+    pub fn new(max_attempts: u32) -> Self {
+        RetryPolicy {
+            max_attempts: max_attempts.max(1),
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            jitter: true,
+        }
+    }
+
+    /// Send every request once
+    pub fn never() -> Self {
+        RetryPolicy::new(1)
+    }
+
+    /// Set the delay after the first failed attempt, doubled after each further failure
+    pub fn set_initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Set the longest delay between two attempts, a longer `Retry-After` is cut to it
+    pub fn set_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Wait a random delay between half and all of the backoff, so that
+    /// clients failing together don't retry together
+    pub fn set_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// View the maximum number of attempts of a request
+    pub fn get_max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Delay before the attempt after `failures` failed ones
+    fn backoff(&self, failures: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .checked_mul(1 << (failures - 1).min(31))
+            .map_or(self.max_backoff, |b| b.min(self.max_backoff));
+
+        if self.jitter {
+            let random = RandomState::new().build_hasher().finish();
+            backoff / 2 + (backoff / 2).mul_f64(random as f64 / u64::MAX as f64)
+        } else {
+            backoff
+        }
+    }
+
+    /// Delay before the attempt after `failures` failed ones, the last of which
+    /// may have asked to retry after some time
+    fn delay(&self, failures: u32, retry_after: Option<Duration>) -> Duration {
+        match retry_after {
+            Some(delay) => delay.min(self.max_backoff),
+            None => self.backoff(failures),
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::never()
+    }
+}
+
+/// Outcome of a request the policy gave up retrying
+pub(crate) enum Reply {
+    /// A 2xx response, body unread
+    Success(Response),
+    /// Any other status, with the response body
+    Failure(u16, String),
+}
+
+/// Whether a failed response is worth another attempt
+pub(crate) fn is_transient(status: u16, body: &str) -> bool {
+    match status {
+        400 => false,
+        429 | 503 => true,
+        _ => {
+            let body = body.to_lowercase();
+            body.contains("hinted handoff queue") && body.contains("full")
+        }
+    }
+}
+
+/// The delay of a `Retry-After` header
+fn retry_after(response: &Response) -> Option<Duration> {
+    parse_retry_after(
+        response.headers().get(RETRY_AFTER)?.to_str().ok()?,
+        SystemTime::now(),
+    )
+}
+
+/// The delay a `Retry-After` value asks for, in seconds or until an HTTP date
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let seconds = u64::try_from(serialization::parse_http_date(value)?).ok()?;
+    let date = UNIX_EPOCH + Duration::from_secs(seconds);
+    Some(date.duration_since(now).unwrap_or_default())
+}
+
+/// Send a request, retrying transient failures as the policy allows.
+///
+/// Requests whose body can't be cloned are sent once.
+pub(crate) async fn send(
+    policy: RetryPolicy,
+    request: RequestBuilder,
+) -> Result<Reply, error::Error> {
+    let mut request = request;
+    let mut failures = 0;
+
+    loop {
+        let next = if failures + 1 < policy.max_attempts {
+            request.try_clone()
+        } else {
+            None
+        };
+
+        let delay = match request.send().await {
+            Ok(response) => {
+                let status = response.status().as_u16();
+                if response.status().is_success() {
+                    return Ok(Reply::Success(response));
+                }
+                let delay = retry_after(&response);
+                let body = response.text().await?;
+                if next.is_none() || !is_transient(status, &body) {
+                    return Ok(Reply::Failure(status, body));
+                }
+                delay
+            }
+            Err(err) => {
+                if next.is_none() || !err.is_connect() {
+                    return Err(err.into());
+                }
+                None
+            }
+        };
+
+        failures += 1;
+        tokio::time::sleep(policy.delay(failures, delay)).await;
+        request = next.expect("checked above");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_transient, parse_retry_after, RetryPolicy};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn backoff() {
+        let policy = RetryPolicy::new(10)
+            .set_initial_backoff(Duration::from_millis(100))
+            .set_max_backoff(Duration::from_secs(1))
+            .set_jitter(false);
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(4), Duration::from_millis(800));
+        assert_eq!(policy.backoff(5), Duration::from_secs(1));
+        assert_eq!(policy.backoff(64), Duration::from_secs(1));
+
+        let policy = policy.set_jitter(true);
+        for _ in 0..100 {
+            let backoff = policy.backoff(3);
+            assert!(backoff >= Duration::from_millis(200) && backoff <= Duration::from_millis(400));
+        }
+
+        assert_eq!(RetryPolicy::new(0).get_max_attempts(), 1);
+    }
+
+    #[test]
+    fn retry_after() {
+        let policy = RetryPolicy::new(3)
+            .set_max_backoff(Duration::from_secs(10))
+            .set_jitter(false);
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(2))),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(3600))),
+            Duration::from_secs(10)
+        );
+        assert_eq!(policy.delay(2, None), Duration::from_millis(200));
+
+        let now = UNIX_EPOCH + Duration::from_secs(784_111_770);
+        assert_eq!(
+            parse_retry_after(" 120 ", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 1994 08:49:37 GMT", now),
+            Some(Duration::from_secs(7))
+        );
+        assert_eq!(
+            parse_retry_after("Sat, 05 Nov 1994 08:49:37 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn transient() {
+        assert!(is_transient(503, ""));
+        assert!(is_transient(429, ""));
+        assert!(is_transient(
+            500,
+            r#"{"error":"write failed: hinted handoff queue full"}"#
+        ));
+        assert!(!is_transient(400, "hinted handoff queue full"));
+        assert!(!is_transient(500, r#"{"error":"timeout"}"#));
+        assert!(!is_transient(
+            404,
+            r#"{"error":"database not found: \"a\""}"#
+        ));
+    }
+}
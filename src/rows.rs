@@ -0,0 +1,162 @@
+use crate::{serialization, Precision};
+use serde::de::{self, value::MapDeserializer, DeserializeOwned, Deserializer, Visitor};
+use serde_json::{Error, Map, Value};
+
+/// Deserialize a row, column names to values, decoding its `time` column
+/// with the `epoch` the query was sent with
+pub(crate) fn from_row<T: DeserializeOwned>(
+    row: Map<String, Value>,
+    epoch: Option<Precision>,
+) -> Result<T, Error> {
+    let columns = row.into_iter().map(move |(name, value)| {
+        let time = if name == "time" { Some(epoch) } else { None };
+        (name, Column { value, time })
+    });
+    T::deserialize(MapDeserializer::new(columns))
+}
+
+/// The value of a column, `time` is the epoch of the time column
+struct Column {
+    value: Value,
+    time: Option<Option<Precision>>,
+}
+
+impl Column {
+    /// Nanoseconds since the unix epoch of the time column
+    fn nanos(&self, epoch: Option<Precision>) -> Result<i64, Error> {
+        match (&self.value, epoch) {
+            (Value::String(time), _) => serialization::parse_rfc3339(time)
+                .ok_or_else(|| de::Error::custom(format!("invalid RFC3339 time '{}'", time))),
+            (Value::Number(time), Some(epoch)) => time
+                .as_i64()
+                .and_then(|time| time.checked_mul(epoch.nanos_per_unit()))
+                .ok_or_else(|| de::Error::custom(format!("invalid time {}", time))),
+            (Value::Number(_), None) => Err(de::Error::custom(
+                "an integer time can't be decoded without the epoch of the query",
+            )),
+            (value, _) => Err(de::Error::custom(format!("invalid time {}", value))),
+        }
+    }
+}
+
+impl<'de> de::IntoDeserializer<'de, Error> for Column {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! forward_to_value {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                self.value.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Column {
+    type Error = Error;
+
+    forward_to_value! {
+        deserialize_any deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_i64 deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32
+        deserialize_u64 deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char
+        deserialize_bytes deserialize_byte_buf deserialize_unit
+        deserialize_seq deserialize_map deserialize_identifier deserialize_ignored_any
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    /// An integer time decodes as RFC3339 too
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.time {
+            Some(epoch @ Some(_)) if self.value.is_number() => {
+                visitor.visit_string(serialization::format_rfc3339(self.nanos(epoch)?))
+            }
+            _ => self.value.deserialize_str(visitor),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.value.deserialize_unit_struct(name, visitor)
+    }
+
+    /// `Timestamp` decodes as nanoseconds, unless it is an integer of an unknown epoch
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.time {
+            Some(None) if name == "Timestamp" && self.value.is_number() => {
+                self.value.deserialize_newtype_struct(name, visitor)
+            }
+            Some(epoch) if name == "Timestamp" => {
+                serde_json::json!({ "Nanoseconds": self.nanos(epoch)? })
+                    .deserialize_newtype_struct(name, visitor)
+            }
+            _ => self.value.deserialize_newtype_struct(name, visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.value.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.value.deserialize_tuple_struct(name, len, visitor)
+    }
+
+    /// `SystemTime` decodes from its seconds and nanoseconds since the unix epoch
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.time {
+            Some(epoch) if name == "SystemTime" => {
+                let nanos = self.nanos(epoch)?;
+                if nanos < 0 {
+                    return Err(de::Error::custom("time before the unix epoch"));
+                }
+                serde_json::json!({
+                    "secs_since_epoch": nanos / 1_000_000_000,
+                    "nanos_since_epoch": nanos % 1_000_000_000,
+                })
+                .deserialize_struct(name, fields, visitor)
+            }
+            _ => self.value.deserialize_struct(name, fields, visitor),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.value.deserialize_enum(name, variants, visitor)
+    }
+}
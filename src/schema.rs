@@ -0,0 +1,213 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{fmt, str::FromStr, time::Duration};
+
+use crate::{error::Error, influxql::InfluxDuration};
+
+/// Type of a field, as reported by `SHOW FIELD KEYS`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    /// float
+    Float,
+    /// Integer
+    Integer,
+    /// Unsigned integer
+    Unsigned,
+    /// string
+    String,
+    /// Bool
+    Boolean,
+}
+
+/// A field key and its type
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FieldKey {
+    /// field key
+    #[serde(rename = "fieldKey")]
+    pub name: String,
+    /// field type
+    #[serde(rename = "fieldType")]
+    pub field_type: FieldType,
+}
+
+/// A retention policy, as reported by `SHOW RETENTION POLICIES`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RetentionPolicy {
+    /// name
+    pub name: String,
+    /// how long data is kept
+    #[serde(deserialize_with = "zero_is_infinite")]
+    pub duration: InfluxDuration,
+    /// time range covered by each shard group
+    #[serde(rename = "shardGroupDuration")]
+    pub shard_group_duration: InfluxDuration,
+    /// number of copies of the data in a cluster
+    #[serde(rename = "replicaN")]
+    pub replication: u32,
+    /// whether writes without a retention policy go to this one
+    pub default: bool,
+}
+
+/// A continuous query, as reported by `SHOW CONTINUOUS QUERIES`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ContinuousQuery {
+    /// name
+    pub name: String,
+    /// the whole `CREATE CONTINUOUS QUERY` statement
+    pub query: String,
+}
+
+/// A user, as reported by `SHOW USERS`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct UserInfo {
+    /// name
+    pub user: String,
+    /// whether the user has cluster administration privileges
+    pub admin: bool,
+}
+
+/// Privilege of a user on a database
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Privilege {
+    /// Read only
+    #[serde(rename = "READ")]
+    Read,
+    /// Write only
+    #[serde(rename = "WRITE")]
+    Write,
+    /// Read and write
+    #[serde(rename = "ALL PRIVILEGES", alias = "ALL")]
+    All,
+}
+
+impl fmt::Display for Privilege {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Privilege::Read => write!(f, "READ"),
+            Privilege::Write => write!(f, "WRITE"),
+            Privilege::All => write!(f, "ALL"),
+        }
+    }
+}
+
+impl FromStr for Privilege {
+    type Err = Error;
+
+    /// Parse `read`, `write`, `all` or `all privileges`, in any case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_uppercase().as_str() {
+            "READ" => Ok(Privilege::Read),
+            "WRITE" => Ok(Privilege::Write),
+            "ALL" | "ALL PRIVILEGES" => Ok(Privilege::All),
+            _ => Err(Error::InvalidArgument(format!("Invalid privilege: {}", s))),
+        }
+    }
+}
+
+/// A query running on the server, as reported by `SHOW QUERIES`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RunningQuery {
+    /// id to pass to `Client::kill_query`
+    pub qid: u64,
+    /// text of the query, as formatted by the server
+    pub query: String,
+    /// database the query runs on
+    pub database: String,
+    /// time since the query started, truncated by the server to its largest unit
+    #[serde(deserialize_with = "finite_duration")]
+    pub duration: Duration,
+}
+
+/// A duration the server never reports as infinite
+fn finite_duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    match InfluxDuration::deserialize(deserializer)? {
+        InfluxDuration::Finite(d) => Ok(d),
+        InfluxDuration::Infinite => Err(serde::de::Error::custom("infinite query duration")),
+    }
+}
+
+/// The server reports an infinite duration as `0s`
+fn zero_is_infinite<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<InfluxDuration, D::Error> {
+    match InfluxDuration::deserialize(deserializer)? {
+        InfluxDuration::Finite(d) if d == Duration::from_secs(0) => Ok(InfluxDuration::Infinite),
+        duration => Ok(duration),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn retention_policy_test() {
+        let policies: Vec<RetentionPolicy> = serde_json::from_str(
+            r#"[{"name":"autogen","duration":"0s","shardGroupDuration":"168h0m0s","replicaN":1,"default":true},
+                {"name":"short","duration":"1h0m0s","shardGroupDuration":"1h0m0s","replicaN":2,"default":false}]"#,
+        )
+        .unwrap();
+        assert_eq!(policies[0].duration, InfluxDuration::Infinite);
+        assert_eq!(
+            policies[0].shard_group_duration,
+            InfluxDuration::Finite(Duration::from_secs(7 * 86400))
+        );
+        assert!(policies[0].default);
+        assert_eq!(
+            policies[1].duration,
+            InfluxDuration::Finite(Duration::from_secs(3600))
+        );
+        assert_eq!(policies[1].replication, 2);
+    }
+
+    #[test]
+    fn privilege_test() {
+        assert_eq!("read".parse::<Privilege>().unwrap(), Privilege::Read);
+        assert_eq!(
+            "ALL PRIVILEGES".parse::<Privilege>().unwrap(),
+            Privilege::All
+        );
+        assert!("NO PRIVILEGES".parse::<Privilege>().is_err());
+        assert_eq!(Privilege::Write.to_string(), "WRITE");
+
+        let privileges: Vec<Privilege> =
+            serde_json::from_str(r#"["READ","ALL PRIVILEGES","ALL"]"#).unwrap();
+        assert_eq!(
+            privileges,
+            vec![Privilege::Read, Privilege::All, Privilege::All]
+        );
+    }
+
+    #[test]
+    fn running_query_test() {
+        let queries: Vec<RunningQuery> = serde_json::from_str(
+            r#"[{"qid":7,"query":"SELECT * FROM cpu","database":"test","duration":"1m5s","status":"running"},
+                {"qid":8,"query":"SHOW QUERIES","database":"","duration":"52µs","status":"running"}]"#,
+        )
+        .unwrap();
+        assert_eq!(queries[0].qid, 7);
+        assert_eq!(queries[0].duration, Duration::from_secs(65));
+        assert_eq!(queries[1].duration, Duration::from_micros(52));
+    }
+
+    #[test]
+    fn field_key_test() {
+        let keys: Vec<FieldKey> = serde_json::from_str(
+            r#"[{"fieldKey":"usage","fieldType":"float"},{"fieldKey":"count","fieldType":"unsigned"}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            keys,
+            vec![
+                FieldKey {
+                    name: "usage".to_string(),
+                    field_type: FieldType::Float
+                },
+                FieldKey {
+                    name: "count".to_string(),
+                    field_type: FieldType::Unsigned
+                },
+            ]
+        );
+    }
+}
@@ -1,74 +1,21 @@
-use crate::{Point, Value};
-use std::borrow::Borrow;
-
-/// Resolve the points to line protocol format
-pub(crate) fn line_serialization<'a>(
-    points: impl IntoIterator<Item = impl Borrow<Point<'a>>>,
-) -> String {
-    let mut line = String::new();
-
-    for point in points {
-        let point: &Point = point.borrow();
-        line.push_str(&escape_measurement(&point.measurement));
-
-        for (tag, value) in &point.tags {
-            line.push(',');
-            line.push_str(&escape_keys_and_tags(tag));
-            line.push('=');
-
-            match value {
-                Value::String(s) => line.push_str(&escape_keys_and_tags(s)),
-                Value::Float(f) => line.push_str(f.to_string().as_str()),
-                Value::Integer(i) => line.push_str(i.to_string().as_str()),
-                Value::Boolean(b) => line.push_str({
-                    if *b {
-                        "true"
-                    } else {
-                        "false"
-                    }
-                }),
-            }
-        }
-
-        let mut was_first = true;
-
-        for (field, value) in &point.fields {
-            line.push({
-                if was_first {
-                    was_first = false;
-                    ' '
-                } else {
-                    ','
-                }
-            });
-            line.push_str(&escape_keys_and_tags(field));
-            line.push('=');
-
-            match value {
-                Value::String(s) => {
-                    line.push_str(&escape_string_field_value(&s.replace("\\\"", "\\\\\"")))
-                }
-                Value::Float(f) => line.push_str(&f.to_string()),
-                Value::Integer(i) => line.push_str(&format!("{i}i")),
-                Value::Boolean(b) => line.push_str({
-                    if *b {
-                        "true"
-                    } else {
-                        "false"
-                    }
-                }),
-            }
-        }
+use crate::client::Compression;
+use flate2::{write::GzEncoder, Compression as Level};
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
 
-        if let Some(t) = point.timestamp {
-            line.push(' ');
-            line.push_str(&t.to_string());
+/// Compress a write body according to `compression`, returning whether it was gzipped
+pub(crate) fn compress(body: Vec<u8>, compression: Compression) -> io::Result<(Vec<u8>, bool)> {
+    match compression {
+        Compression::Gzip { level, min_size } if body.len() >= min_size => {
+            let mut encoder =
+                GzEncoder::new(Vec::with_capacity(body.len() / 4), Level::new(level.min(9)));
+            encoder.write_all(&body)?;
+            Ok((encoder.finish()?, true))
         }
-
-        line.push('\n')
+        _ => Ok((body, false)),
     }
-
-    line
 }
 
 #[inline]
@@ -82,83 +29,331 @@ pub(crate) fn quote_ident(value: &str) -> String {
     )
 }
 
+/// Quote a regex between slashes.
+///
+/// The InfluxQL lexer only unescapes `\/`, other escapes are kept as they are.
+pub(crate) fn quote_regex(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('/');
+
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped) => {
+                    quoted.push('\\');
+                    quoted.push(escaped);
+                }
+                // A lone trailing backslash would escape the closing slash
+                None => quoted.push_str("\\\\"),
+            },
+            '/' => quoted.push_str("\\/"),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('/');
+    quoted
+}
+
 #[inline]
 pub(crate) fn quote_literal(value: &str) -> String {
     format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
 }
 
-#[inline]
+/// The message of an error response, `{"error": "..."}` from 1.x,
+/// `{"code": "...", "message": "..."}` from 2.x or plain text
 pub(crate) fn conversion(value: &str) -> String {
-    value
-        .replace('\'', "")
-        .replace('\"', "")
-        .replace('\\', "")
-        .trim()
-        .to_string()
+    #[derive(serde::Deserialize)]
+    struct ErrorBody {
+        #[serde(alias = "message")]
+        error: String,
+    }
+
+    match serde_json::from_str::<ErrorBody>(value) {
+        Ok(body) => body.error.trim().to_string(),
+        Err(_) => value.trim().to_string(),
+    }
 }
 
-#[inline]
-fn escape_keys_and_tags(value: impl AsRef<str>) -> String {
-    value
-        .as_ref()
-        .replace(',', "\\,")
-        .replace('=', "\\=")
-        .replace(' ', "\\ ")
+/// Days since the unix epoch of a date of the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
 }
 
-#[inline]
-fn escape_measurement(value: &str) -> String {
-    value.replace(',', "\\,").replace(' ', "\\ ")
+/// The date of a number of days since the unix epoch, inverse of `days_from_civil`
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
 }
 
-#[inline]
-fn escape_string_field_value(value: &str) -> String {
-    format!("\"{}\"", value.replace('\"', "\\\""))
+/// Nanoseconds since the unix epoch of an RFC3339 time, as the server returns them,
+/// such as `2020-01-01T00:00:00.5Z` or `2020-01-01T01:00:00+01:00`
+pub(crate) fn parse_rfc3339(value: &str) -> Option<i64> {
+    let number = |range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = value.get(range)?;
+        if digits.bytes().all(|b| b.is_ascii_digit()) {
+            digits.parse().ok()
+        } else {
+            None
+        }
+    };
+    let bytes = value.as_bytes();
+    if bytes.len() < 20
+        || bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || !matches!(bytes[10], b'T' | b't')
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+    {
+        return None;
+    }
+
+    let days = days_from_civil(number(0..4)?, number(5..7)?, number(8..10)?);
+    let seconds = number(11..13)? * 3600 + number(14..16)? * 60 + number(17..19)?;
+
+    let mut rest = &value[19..];
+    let mut nanos = 0;
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let digits = fraction
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(fraction.len());
+        if digits == 0 || digits > 9 {
+            return None;
+        }
+        nanos = fraction[..digits].parse::<i64>().ok()? * 10_i64.pow(9 - digits as u32);
+        rest = &fraction[digits..];
+    }
+
+    let offset = match rest {
+        "Z" | "z" => 0,
+        _ if rest.len() == 6 && rest.as_bytes()[3] == b':' => {
+            let sign = match rest.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let offset = &rest[1..];
+            let hours: i64 = offset[..2].parse().ok()?;
+            let minutes: i64 = offset[3..].parse().ok()?;
+            sign * (hours * 3600 + minutes * 60)
+        }
+        _ => return None,
+    };
+
+    (days * 86_400 + seconds - offset)
+        .checked_mul(1_000_000_000)?
+        .checked_add(nanos)
+}
+
+/// Seconds since the unix epoch of an HTTP date, in the preferred
+/// `Sun, 06 Nov 1994 08:49:37 GMT` format, the obsolete ones are not supported
+pub(crate) fn parse_http_date(value: &str) -> Option<i64> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let (day, month, year, time) = match parts[..] {
+        [weekday, day, month, year, time, "GMT"] if weekday.ends_with(',') => {
+            (day, month, year, time)
+        }
+        _ => return None,
+    };
+    let month = MONTHS.iter().position(|m| *m == month)? as i64 + 1;
+    let time: Vec<i64> = time
+        .split(':')
+        .map(|n| n.parse().ok())
+        .collect::<Option<_>>()?;
+    let seconds = match time[..] {
+        [hours, minutes, seconds] => hours * 3600 + minutes * 60 + seconds,
+        _ => return None,
+    };
+
+    Some(days_from_civil(year.parse().ok()?, month, day.parse().ok()?) * 86_400 + seconds)
+}
+
+/// Format nanoseconds since the unix epoch in UTC as the server does,
+/// without trailing zeros in the fraction of a second
+pub(crate) fn format_rfc3339(nanos: i64) -> String {
+    let seconds = nanos.div_euclid(1_000_000_000);
+    let fraction = nanos.rem_euclid(1_000_000_000);
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    let time = seconds.rem_euclid(86_400);
+
+    let mut formatted = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    );
+    if fraction != 0 {
+        formatted.push_str(format!(".{:09}", fraction).trim_end_matches('0'));
+    }
+    formatted.push('Z');
+    formatted
+}
+
+/// Format a duration as an InfluxQL duration literal, such as `1h30m`
+pub(crate) fn format_duration(duration: Duration) -> String {
+    const UNITS: [(&str, u128); 8] = [
+        ("w", 7 * 24 * 3600 * 1_000_000_000),
+        ("d", 24 * 3600 * 1_000_000_000),
+        ("h", 3600 * 1_000_000_000),
+        ("m", 60 * 1_000_000_000),
+        ("s", 1_000_000_000),
+        ("ms", 1_000_000),
+        ("u", 1_000),
+        ("ns", 1),
+    ];
+
+    let mut nanos = duration.as_nanos();
+    if nanos == 0 {
+        return "0s".to_string();
+    }
+
+    let mut literal = String::new();
+    for (unit, size) in UNITS.iter() {
+        if nanos >= *size {
+            literal.push_str(&(nanos / size).to_string());
+            literal.push_str(unit);
+            nanos %= size;
+        }
+    }
+    literal
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{Point, Points};
 
     #[test]
-    fn line_serialization_test() {
-        let point = Point::new("test")
-            .add_field("somefield", Value::Integer(65))
-            .add_tag("sometag", Value::Boolean(false));
-        let points = Points::new(point);
+    fn compress_test() {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
 
+        let body = b"cpu,host=a usage=0.5\n".repeat(100);
+        let gzip = Compression::Gzip {
+            level: 6,
+            min_size: 1024,
+        };
+
+        let (compressed, gzipped) = compress(body.clone(), gzip).unwrap();
+        assert!(gzipped);
+        assert!(compressed.len() < body.len());
+        let mut decompressed = Vec::new();
+        GzDecoder::new(&compressed[..])
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, body);
+
+        let small = b"cpu usage=0.5\n".to_vec();
+        assert_eq!(
+            compress(small.clone(), gzip).unwrap(),
+            (small.clone(), false)
+        );
         assert_eq!(
-            line_serialization(&points),
-            "test,sometag=false somefield=65i\n"
-        )
+            compress(small.clone(), Compression::None).unwrap(),
+            (small, false)
+        );
     }
 
     #[test]
-    fn escape_keys_and_tags_test() {
-        assert_eq!(
-            escape_keys_and_tags("foo, hello=world"),
-            "foo\\,\\ hello\\=world"
-        )
+    fn quote_ident_test() {
+        assert_eq!(quote_ident("root"), "\"root\"")
     }
 
     #[test]
-    fn escape_measurement_test() {
-        assert_eq!(escape_measurement("foo, hello"), "foo\\,\\ hello")
+    fn quote_regex_test() {
+        assert_eq!(quote_regex("^web/[0-9]+$"), r"/^web\/[0-9]+$/");
+        assert_eq!(quote_regex(r"^a\/b\.c"), r"/^a\/b\.c/");
+        assert_eq!(quote_regex(r"a\"), r"/a\\/");
+        assert_eq!(quote_regex(r"a\\"), r"/a\\/");
+        assert_eq!(quote_regex(r"a\\\"), r"/a\\\\/");
     }
 
     #[test]
-    fn escape_string_field_value_test() {
-        assert_eq!(escape_string_field_value("\"foo"), "\"\\\"foo\"")
+    fn quote_literal_test() {
+        assert_eq!(quote_literal("root"), "\'root\'")
     }
 
     #[test]
-    fn quote_ident_test() {
-        assert_eq!(quote_ident("root"), "\"root\"")
+    fn format_duration_test() {
+        assert_eq!(format_duration(Duration::from_secs(0)), "0s");
+        assert_eq!(format_duration(Duration::from_secs(5400)), "1h30m");
+        assert_eq!(format_duration(Duration::from_secs(8 * 86400)), "1w1d");
+        assert_eq!(
+            format_duration(Duration::from_nanos(1_500_001)),
+            "1ms500u1ns"
+        );
     }
 
     #[test]
-    fn quote_literal_test() {
-        assert_eq!(quote_literal("root"), "\'root\'")
+    fn conversion_test() {
+        assert_eq!(
+            conversion("{\"error\":\"database not found: \\\"it's\\\"\"}\n"),
+            "database not found: \"it's\""
+        );
+        assert_eq!(conversion(" 404 page not found\n"), "404 page not found");
+    }
+
+    #[test]
+    fn rfc3339_test() {
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(
+            parse_rfc3339("2020-01-01T00:00:00.5Z"),
+            Some(1_577_836_800_500_000_000)
+        );
+        assert_eq!(
+            parse_rfc3339("2020-01-01T01:00:00.000000001+01:00"),
+            Some(1_577_836_800_000_000_001)
+        );
+        assert_eq!(parse_rfc3339("1969-12-31T23:59:59.999Z"), Some(-1_000_000));
+        assert_eq!(parse_rfc3339("2020-01-01 00:00:00Z"), None);
+        assert_eq!(parse_rfc3339("2020-01-01T00:00:00"), None);
+        assert_eq!(parse_rfc3339("2020-01-01T00:00:00.Z"), None);
+
+        assert_eq!(format_rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            format_rfc3339(1_577_836_800_500_000_000),
+            "2020-01-01T00:00:00.5Z"
+        );
+        assert_eq!(format_rfc3339(-1_000_000), "1969-12-31T23:59:59.999Z");
+        assert_eq!(
+            format_rfc3339(951_782_400_000_000_001),
+            "2000-02-29T00:00:00.000000001Z"
+        );
+        assert_eq!(
+            parse_rfc3339(&format_rfc3339(951_782_400_000_000_001)),
+            Some(951_782_400_000_000_001)
+        );
+    }
+
+    #[test]
+    fn parse_http_date_test() {
+        assert_eq!(
+            parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(784_111_777)
+        );
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), None);
+        assert_eq!(parse_http_date("Sun Nov  6 08:49:37 1994"), None);
+        assert_eq!(parse_http_date("120"), None);
     }
 }
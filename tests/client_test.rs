@@ -1,6 +1,10 @@
-use influx_db_client::{point, points, reqwest::Url, Client, Point, Points, Precision, UdpClient};
+use influx_db_client::{
+    point, points, reqwest::Url, BatchOptions, BatchWriter, Client, Compression, Error, Point,
+    Points, Precision, QueryParam, RetryPolicy, UdpClient,
+};
 use std::fs::File;
-use std::io::Read;
+use std::io::{Read, Write};
+use std::net::TcpListener;
 use std::thread::sleep;
 use std::time::Duration;
 
@@ -161,7 +165,7 @@ fn use_https() {
         .unwrap()
         .to_owned();
     let output = Command::new("openssl")
-        .args(&[
+        .args([
             "req",
             "-x509",
             "-nodes",
@@ -262,4 +266,988 @@ bind-address = "127.0.0.1:{rpc_port}"
     });
 
     influxdb_server.kill().unwrap();
+    influxdb_server.wait().unwrap();
+}
+
+#[test]
+fn strict_validation() {
+    block_on(async {
+        let client = Client::default().set_strict_validation(true);
+        let points = points![
+            point!("test5").add_field("foo", "bar"),
+            point!("test5").add_field("time", 1)
+        ];
+
+        match client.write_points(&points, None, None).await {
+            Err(Error::InvalidPoint { index, key, .. }) => {
+                assert_eq!(index, 1);
+                assert_eq!(key, "time");
+            }
+            other => panic!("expected InvalidPoint, got {:?}", other),
+        }
+    });
+}
+
+#[test]
+fn query_as() {
+    #[derive(serde::Deserialize)]
+    struct Row {
+        time: i64,
+        host: String,
+        foo: String,
+    }
+
+    block_on(async {
+        let mut client = Client::default().set_authentication("root", "root");
+        client.switch_database("test_query_as");
+        client.create_database(client.get_db()).await.unwrap();
+        let point = point!("test6")
+            .add_tag("host", "a")
+            .add_field("foo", "bar")
+            .add_timestamp(1_508_981_970);
+        client.write_point(point, None, None).await.unwrap();
+
+        let rows: Vec<Row> = client
+            .query_as("select * from test6", Some(Precision::Seconds))
+            .await
+            .unwrap();
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].time, 1_508_981_970);
+        assert_eq!(rows[0].host, "a");
+        assert_eq!(rows[0].foo, "bar");
+
+        client.drop_database(client.get_db()).await.unwrap();
+    });
+}
+
+#[test]
+fn use_gzip() {
+    block_on(async {
+        let mut client = Client::default()
+            .set_authentication("root", "root")
+            .set_compression(Compression::Gzip {
+                level: 9,
+                min_size: 0,
+            });
+        client.switch_database("test_use_gzip");
+        client.create_database(client.get_db()).await.unwrap();
+
+        let points: Points = (0..1000)
+            .map(|i| point!("test7").add_field("value", i).add_timestamp(i))
+            .collect();
+        client.write_points(&points, None, None).await.unwrap();
+
+        client
+            .write_points_with_compression(&points, None, None, Compression::None)
+            .await
+            .unwrap();
+
+        client.drop_database(client.get_db()).await.unwrap();
+    });
+}
+
+#[test]
+fn use_client_defaults() {
+    block_on(async {
+        let mut client = Client::default()
+            .set_authentication("root", "root")
+            .set_precision(Precision::Nanoseconds)
+            .set_retention_policy("short");
+        client.switch_database("test_use_client_defaults");
+        client.create_database(client.get_db()).await.unwrap();
+        client
+            .create_retention_policy("short", "1h".parse().unwrap(), 1, None, false, None)
+            .await
+            .unwrap();
+
+        assert_eq!(client.get_precision(), Precision::Nanoseconds);
+        assert_eq!(client.get_retention_policy(), Some("short"));
+
+        let point = point!("test8")
+            .add_field("foo", "bar")
+            .add_timestamp(std::time::SystemTime::now());
+        client.write_point(point, None, None).await.unwrap();
+
+        let res = client
+            .query("select * from \"short\".\"test8\"", None)
+            .await
+            .unwrap();
+        assert!(res.unwrap()[0].series.is_some());
+
+        client.drop_database(client.get_db()).await.unwrap();
+    });
+}
+
+#[test]
+fn use_batch_writer() {
+    block_on(async {
+        let mut client = Client::default().set_authentication("root", "root");
+        client.switch_database("test_use_batch_writer");
+        client.create_database(client.get_db()).await.unwrap();
+
+        let writer = BatchWriter::new(
+            client.clone(),
+            BatchOptions::new()
+                .set_max_points(100)
+                .set_flush_interval(Duration::from_millis(50))
+                .set_precision(Precision::Nanoseconds)
+                .set_error_handler(|err, _| panic!("{}", err)),
+        );
+        let handle = writer.handle();
+        for i in 0..250 {
+            handle
+                .write(point!("test9").add_field("value", i).add_timestamp(i))
+                .await
+                .unwrap();
+        }
+        writer.close().await;
+        assert!(handle
+            .try_write(point!("test9").add_field("value", 0))
+            .is_err());
+
+        let res = client
+            .query("select count(value) from test9", None)
+            .await
+            .unwrap()
+            .unwrap();
+        let series = res[0].series.as_ref().unwrap();
+        assert_eq!(series[0].values.as_ref().unwrap()[0][1], 250);
+
+        client.drop_database(client.get_db()).await.unwrap();
+    });
+}
+
+#[test]
+fn batch_writer_reports_failures() {
+    block_on(async {
+        let client = Client::new(Url::parse("http://127.0.0.1:1").unwrap(), "test");
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+
+        let writer = BatchWriter::new(
+            client,
+            BatchOptions::new()
+                .set_max_points(10)
+                .set_error_handler(move |_, points| sender.send(points).unwrap()),
+        );
+        for i in 0..25 {
+            writer
+                .write(point!("test10").add_field("value", i))
+                .await
+                .unwrap();
+        }
+        writer.close().await;
+
+        let mut lost = Vec::new();
+        while let Some(points) = receiver.recv().await {
+            lost.push(points);
+        }
+        lost.sort();
+        assert_eq!(lost, vec![5, 10, 10]);
+    });
+}
+
+#[test]
+fn batch_handles_outlive_writer() {
+    block_on(async {
+        let (url, server) = serve_responses(vec![
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n".to_string(),
+        ]);
+
+        let writer = BatchWriter::new(Client::new(url.clone(), "test"), BatchOptions::new());
+        let handle = writer.handle();
+        drop(writer);
+        handle
+            .write(point!("test12").add_field("value", 1))
+            .await
+            .unwrap();
+        // the last handle drains the queue
+        drop(handle);
+        assert_eq!(server.join().unwrap().len(), 1);
+
+        let writer = BatchWriter::new(
+            Client::new(url, "test"),
+            BatchOptions::new().set_queue_size(1),
+        );
+        let handle = writer.handle();
+        writer.close().await;
+        let err = handle
+            .try_write(point!("test12").add_field("value", 1))
+            .unwrap_err();
+        assert_eq!(err, Error::BatchWriterClosed);
+        assert!(!err.is_retryable());
+    });
+}
+
+/// Serve one canned response per connection, return the requests served.
+/// An empty response leaves its connection open without answering.
+fn serve_responses(responses: Vec<String>) -> (Url, std::thread::JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+    let handle = std::thread::spawn(move || {
+        let mut served = Vec::new();
+        let mut unanswered = Vec::new();
+        for response in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            // read the headers and the body of the request
+            loop {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_lowercase();
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text
+                        .lines()
+                        .find_map(|l| l.strip_prefix("content-length:"))
+                        .map_or(0, |l| l.trim().parse().unwrap());
+                    if request.len() >= end + 4 + length {
+                        break;
+                    }
+                }
+            }
+            if response.is_empty() {
+                unanswered.push(stream);
+            } else {
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            served.push(String::from_utf8_lossy(&request).into_owned());
+        }
+        served
+    });
+    (url, handle)
+}
+
+/// A query string parameter of a request received by `serve_responses`
+fn request_param(request: &str, key: &str) -> Option<String> {
+    let target = request.split(' ').nth(1)?;
+    let url = Url::parse("http://localhost")
+        .unwrap()
+        .join(target)
+        .unwrap();
+    url.query_pairs()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.into_owned())
+}
+
+#[test]
+fn retry_transient_failures() {
+    block_on(async {
+        let (url, server) = serve_responses(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            "HTTP/1.1 429 Too Many Requests\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n".to_string(),
+        ]);
+        let client = Client::new(url, "test")
+            .set_retry_policy(RetryPolicy::new(3).set_initial_backoff(Duration::from_millis(1)));
+        client
+            .write_point(point!("test11").add_field("value", 1), None, None)
+            .await
+            .unwrap();
+        assert_eq!(server.join().unwrap().len(), 3);
+    });
+}
+
+#[test]
+fn never_retry_bad_request() {
+    block_on(async {
+        let body = r#"{"error":"unable to parse 'test12 value=': missing field value"}"#;
+        let response = format!(
+            "HTTP/1.1 400 Bad Request\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let (url, server) = serve_responses(vec![response]);
+        let client = Client::new(url, "test")
+            .set_retry_policy(RetryPolicy::new(3).set_initial_backoff(Duration::from_millis(1)));
+        let err = client
+            .write_point(point!("test12").add_field("value", 1), None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::SyntaxError { status: 400, .. }));
+        assert!(!err.is_retryable());
+        assert_eq!(server.join().unwrap().len(), 1);
+    });
+}
+
+#[test]
+fn query_chunked_stream() {
+    block_on(async {
+        use futures::TryStreamExt;
+
+        let body = concat!(
+            r#"{"results":[{"statement_id":0,"series":[{"name":"test13","columns":["time","value"],"values":[[1,1]],"partial":true}],"partial":true}]}"#,
+            "\n",
+            r#"{"results":[{"statement_id":0,"series":[{"name":"test13","columns":["time","value"],"values":[[2,2]]}]}]}"#,
+            "\n",
+        );
+        let (url, server) = serve_responses(vec![format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )]);
+        let client = Client::new(url, "test");
+
+        let chunks: Vec<_> = client
+            .query_chunked("select * from test13", None, Some(1))
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].results.as_ref().unwrap()[0].partial);
+        assert!(!chunks[1].results.as_ref().unwrap()[0].partial);
+        server.join().unwrap();
+    });
+}
+
+#[test]
+fn query_with_params() {
+    block_on(async {
+        let body = r#"{"results":[{"statement_id":0}]}"#;
+        let (url, server) = serve_responses(vec![format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )]);
+        let client = Client::new(url, "test");
+
+        client
+            .query_with_params(
+                "select * from test14 where host = $host and time > now() - $window",
+                &[
+                    ("host", QueryParam::from("it's")),
+                    ("window", QueryParam::from(Duration::from_secs(3600))),
+                ],
+                None,
+            )
+            .await
+            .unwrap();
+
+        let request = server.join().unwrap().remove(0);
+        assert_eq!(
+            request_param(&request, "params").as_deref(),
+            Some(r#"{"host":"it's","window":{"duration":"1h"}}"#)
+        );
+    });
+}
+
+/// A 200 response with a json body
+fn json_response(body: &str) -> String {
+    format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )
+}
+
+#[test]
+fn list_schema_typed() {
+    block_on(async {
+        use influx_db_client::schema::{FieldKey, FieldType};
+
+        let (url, server) = serve_responses(vec![
+            json_response(
+                r#"{"results":[{"statement_id":0,"series":[{"name":"cpu","columns":["fieldKey","fieldType"],"values":[["usage","float"],["count","integer"]]},{"name":"mem","columns":["fieldKey","fieldType"],"values":[["free","unsigned"]]}]}]}"#,
+            ),
+            json_response(
+                r#"{"results":[{"statement_id":0,"series":[{"name":"cpu","columns":["key","value"],"values":[["host","a"],["host","b"]]}]}]}"#,
+            ),
+            json_response(r#"{"results":[{"statement_id":0,"error":"database not found: test"}]}"#),
+        ]);
+        let client = Client::new(url, "test");
+
+        let fields = client.list_field_keys(None).await.unwrap();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(
+            fields["cpu"][0],
+            FieldKey {
+                name: "usage".to_string(),
+                field_type: FieldType::Float
+            }
+        );
+        assert_eq!(fields["mem"][0].field_type, FieldType::Unsigned);
+
+        let values = client.list_tag_values("host", Some("cpu")).await.unwrap();
+        assert_eq!(values["cpu"], vec!["a", "b"]);
+
+        let err = client.list_measurements(Some("^c")).await.unwrap_err();
+        assert!(matches!(err, Error::DataBaseDoesNotExist { .. }));
+
+        let requests = server.join().unwrap();
+        assert!(requests[1].contains("WITH+KEY+%3D+%22host%22"));
+        assert!(requests[2].contains("WITH+MEASUREMENT+%3D%7E+%2F%5Ec%2F"));
+    });
+}
+
+#[test]
+fn series_cardinality() {
+    block_on(async {
+        let (url, server) = serve_responses(vec![
+            json_response(
+                r#"{"results":[{"statement_id":0,"series":[{"columns":["cardinality estimation"],"values":[[42]]}]}]}"#,
+            ),
+            json_response(
+                r#"{"results":[{"statement_id":0,"series":[{"name":"cpu","columns":["count"],"values":[[7]]}]}]}"#,
+            ),
+        ]);
+        let client = Client::new(url, "test");
+
+        assert_eq!(client.show_series_cardinality(None).await.unwrap(), 42);
+        assert_eq!(
+            client.show_series_cardinality(Some("cpu")).await.unwrap(),
+            7
+        );
+
+        let requests = server.join().unwrap();
+        assert_eq!(
+            request_param(&requests[0], "q").unwrap(),
+            "SHOW SERIES CARDINALITY"
+        );
+        assert_eq!(
+            request_param(&requests[1], "q").unwrap(),
+            "SHOW SERIES CARDINALITY FROM \"cpu\""
+        );
+    });
+}
+
+#[test]
+fn list_schema() {
+    block_on(async {
+        let mut client = Client::default().set_authentication("root", "root");
+        client.switch_database("test_list_schema");
+        client.create_database(client.get_db()).await.unwrap();
+
+        let point = point!("test15")
+            .add_tag("host", "server01")
+            .add_field("usage", 0.5);
+        client.write_point(point, None, None).await.unwrap();
+
+        assert!(client
+            .list_databases()
+            .await
+            .unwrap()
+            .contains(&"test_list_schema".to_string()));
+        assert_eq!(
+            client.list_measurements(Some("^test1")).await.unwrap(),
+            vec!["test15"]
+        );
+        assert_eq!(
+            client.list_tag_keys(Some("test15")).await.unwrap()["test15"],
+            vec!["host"]
+        );
+        assert_eq!(
+            client.list_tag_values("host", None).await.unwrap()["test15"],
+            vec!["server01"]
+        );
+        assert_eq!(
+            client.list_field_keys(Some("test15")).await.unwrap()["test15"][0].name,
+            "usage"
+        );
+        assert_eq!(
+            client.list_series(None).await.unwrap(),
+            vec!["test15,host=server01"]
+        );
+        assert_eq!(client.show_series_cardinality(None).await.unwrap(), 1);
+
+        client.drop_database(client.get_db()).await.unwrap();
+    });
+}
+
+#[test]
+fn use_retention_policies() {
+    block_on(async {
+        use influx_db_client::influxql::InfluxDuration;
+
+        let mut client = Client::default().set_authentication("root", "root");
+        client.switch_database("test_use_retention_policies");
+        client.create_database(client.get_db()).await.unwrap();
+
+        let hour = InfluxDuration::Finite(Duration::from_secs(3600));
+        client
+            .create_retention_policy("short", hour, 1, Some(hour), false, None)
+            .await
+            .unwrap();
+        client
+            .alter_retention_policy("short", Some("2h".parse().unwrap()), None, None, true, None)
+            .await
+            .unwrap();
+        assert!(matches!(
+            client
+                .alter_retention_policy("short", None, None, None, false, None)
+                .await,
+            Err(Error::InvalidArgument(_))
+        ));
+
+        let policies = client.list_retention_policies(None).await.unwrap();
+        let autogen = policies.iter().find(|rp| rp.name == "autogen").unwrap();
+        assert_eq!(autogen.duration, InfluxDuration::Infinite);
+        assert!(!autogen.default);
+        let short = policies.iter().find(|rp| rp.name == "short").unwrap();
+        assert_eq!(short.duration, "2h".parse().unwrap());
+        assert_eq!(short.shard_group_duration, hour);
+        assert!(short.default);
+
+        client.drop_retention_policy("short", None).await.unwrap();
+        client.drop_database(client.get_db()).await.unwrap();
+    });
+}
+
+#[test]
+fn use_continuous_queries() {
+    block_on(async {
+        use influx_db_client::influxql::{Aggregate, Field, SelectQuery};
+
+        let mut client = Client::default().set_authentication("root", "root");
+        client.switch_database("test_use_continuous_queries");
+        client.create_database(client.get_db()).await.unwrap();
+
+        let downsample = SelectQuery::new("test16")
+            .add_field(Field::aggregate(Aggregate::Mean, "value").set_alias("value"))
+            .set_into("test16_1h")
+            .set_group_by_time(Duration::from_secs(3600), None);
+        client
+            .create_continuous_query(
+                "test16_1h",
+                None,
+                &downsample,
+                Some(Duration::from_secs(1800)),
+                Some(Duration::from_secs(7200)),
+            )
+            .await
+            .unwrap();
+        client
+            .create_continuous_query(
+                "test16_max",
+                None,
+                "select max(value) into test16_max from test16 group by time(1h)",
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let queries = client.list_continuous_queries().await.unwrap();
+        let queries = &queries["test_use_continuous_queries"];
+        assert_eq!(queries.len(), 2);
+        assert!(queries
+            .iter()
+            .any(|cq| cq.name == "test16_1h" && cq.query.contains("RESAMPLE EVERY 30m FOR 2h")));
+
+        client
+            .drop_continuous_query("test16_1h", None)
+            .await
+            .unwrap();
+        client
+            .drop_continuous_query("test16_max", None)
+            .await
+            .unwrap();
+        client.drop_database(client.get_db()).await.unwrap();
+    });
+}
+
+#[test]
+fn retention_policy_statement_errors() {
+    block_on(async {
+        use influx_db_client::influxql::InfluxDuration;
+
+        let (url, _) = serve_responses(vec![
+            json_response(
+                r#"{"results":[{"statement_id":0,"error":"retention policy duration must be at least 1h0m0s"}]}"#,
+            ),
+            json_response(
+                r#"{"results":[{"statement_id":0,"error":"retention policy not found: short"}]}"#,
+            ),
+        ]);
+        let client = Client::new(url, "test");
+
+        assert!(matches!(
+            client
+                .create_retention_policy(
+                    "short",
+                    InfluxDuration::Finite(Duration::from_secs(60)),
+                    1,
+                    None,
+                    false,
+                    None
+                )
+                .await,
+            Err(Error::Server { status: 200, ref message, .. })
+                if message == "retention policy duration must be at least 1h0m0s"
+        ));
+        assert!(matches!(
+            client
+                .alter_retention_policy("short", None, Some(2), None, false, None)
+                .await,
+            Err(Error::RetentionPolicyDoesNotExist { .. })
+        ));
+    });
+}
+
+#[test]
+fn continuous_query_statement() {
+    block_on(async {
+        use influx_db_client::influxql::{Aggregate, Field, Measurement, SelectQuery};
+
+        let ok = json_response(r#"{"results":[{"statement_id":0}]}"#);
+        let (url, server) = serve_responses(vec![
+            ok.clone(),
+            ok,
+            json_response(
+                r#"{"results":[{"statement_id":0,"error":"continuous query already exists"}]}"#,
+            ),
+        ]);
+        let client = Client::new(url, "test");
+
+        let downsample = SelectQuery::new("cpu")
+            .add_field(Field::aggregate(Aggregate::Mean, "usage"))
+            .set_into(Measurement::backreference().set_retention_policy("long"))
+            .set_group_by_time(Duration::from_secs(3600), None);
+        client
+            .create_continuous_query(
+                "cq",
+                Some("db"),
+                &downsample,
+                None,
+                Some(Duration::from_secs(7200)),
+            )
+            .await
+            .unwrap();
+        client.drop_continuous_query("cq", None).await.unwrap();
+        assert!(matches!(
+            client
+                .create_continuous_query("cq", None, &downsample, None, None)
+                .await,
+            Err(Error::Server { ref message, .. }) if message == "continuous query already exists"
+        ));
+
+        let requests = server.join().unwrap();
+        assert_eq!(
+            request_param(&requests[0], "q").unwrap(),
+            "Create continuous query \"cq\" on \"db\" resample for 2h begin \
+             SELECT mean(\"usage\") INTO \"long\".:MEASUREMENT FROM \"cpu\" GROUP BY time(1h) end"
+        );
+        assert_eq!(
+            request_param(&requests[1], "q").unwrap(),
+            "Drop continuous query \"cq\" on \"test\""
+        );
+    });
+}
+
+#[test]
+fn delete_and_drop_series_statements() {
+    block_on(async {
+        use influx_db_client::influxql::{Condition, Time};
+
+        let ok = json_response(r#"{"results":[{"statement_id":0}]}"#);
+        let (url, server) = serve_responses(vec![
+            ok.clone(),
+            ok,
+            json_response(r#"{"results":[{"statement_id":0,"error":"database not found: test"}]}"#),
+        ]);
+        let client = Client::new(url, "test");
+
+        client
+            .delete_series(
+                Some("cpu"),
+                Some(
+                    Condition::tag_eq("host", "it's")
+                        .and(Condition::time_lt(Time::Ago(Duration::from_secs(86400)))),
+                ),
+            )
+            .await
+            .unwrap();
+        client
+            .drop_series(None, Some(Condition::tag_matches("host", "^web/")))
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            client
+                .drop_series(Some("cpu"), Some(Condition::time_gt(Time::Now)))
+                .await,
+            Err(Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            client.delete_series(None, None).await,
+            Err(Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            client.delete_series(Some("cpu"), None).await,
+            Err(Error::DataBaseDoesNotExist { .. })
+        ));
+
+        let requests = server.join().unwrap();
+        assert_eq!(
+            request_param(&requests[0], "q").unwrap(),
+            "Delete FROM \"cpu\" where (\"host\" = 'it\\'s' AND time < now() - 1d)"
+        );
+        assert_eq!(
+            request_param(&requests[1], "q").unwrap(),
+            "Drop series where \"host\" =~ /^web\\//"
+        );
+    });
+}
+
+#[test]
+fn user_statement_errors() {
+    block_on(async {
+        let error = |message: &str| {
+            json_response(&format!(
+                r#"{{"results":[{{"statement_id":0,"error":"{}"}}]}}"#,
+                message
+            ))
+        };
+        let (url, server) = serve_responses(vec![
+            json_response(r#"{"results":[{"statement_id":0}]}"#),
+            error("user already exists"),
+            error("user not found"),
+            error("user not found"),
+            error("user not found"),
+            error("user not found"),
+        ]);
+        let client = Client::new(url, "test");
+        let not_found = |res: Result<(), Error>| matches!(res, Err(Error::Server { ref message, .. }) if message == "user not found");
+
+        client.create_user("grafana", "it's", true).await.unwrap();
+        assert!(matches!(
+            client.create_user("grafana", "secret", false).await,
+            Err(Error::Server { ref message, .. }) if message == "user already exists"
+        ));
+        assert!(not_found(client.drop_user("nobody").await));
+        assert!(not_found(
+            client.set_user_password("nobody", "secret").await
+        ));
+        assert!(not_found(client.grant_admin_privileges("nobody").await));
+        assert!(not_found(client.revoke_admin_privileges("nobody").await));
+
+        let statements: Vec<_> = server
+            .join()
+            .unwrap()
+            .iter()
+            .map(|request| request_param(request, "q").unwrap())
+            .collect();
+        assert_eq!(
+            statements,
+            vec![
+                "Create user \"grafana\" with password 'it\\'s' with all privileges",
+                "Create user \"grafana\" WITH password 'secret'",
+                "Drop user \"nobody\"",
+                "Set password for \"nobody\"='secret'",
+                "Grant all privileges to \"nobody\"",
+                "Revoke all privileges from \"nobody\"",
+            ]
+        );
+    });
+}
+
+#[test]
+fn sync_user_grants() {
+    block_on(async {
+        use influx_db_client::schema::{Privilege, UserInfo};
+        use std::collections::BTreeMap;
+
+        let ok = json_response(r#"{"results":[{"statement_id":0}]}"#);
+        let (url, server) = serve_responses(vec![
+            json_response(
+                r#"{"results":[{"statement_id":0,"series":[{"columns":["user","admin"],"values":[["admin",true],["grafana",false]]}]}]}"#,
+            ),
+            json_response(
+                r#"{"results":[{"statement_id":0,"series":[{"columns":["database","privilege"],"values":[["logs","WRITE"],["metrics","READ"],["old","ALL PRIVILEGES"],["none","NO PRIVILEGES"]]}]}]}"#,
+            ),
+            ok.clone(),
+            ok.clone(),
+            ok,
+            json_response(r#"{"results":[{"statement_id":0}]}"#),
+            json_response(r#"{"results":[{"statement_id":0,"error":"user not found"}]}"#),
+        ]);
+        let client = Client::new(url, "test");
+
+        assert_eq!(
+            client.list_users().await.unwrap(),
+            vec![
+                UserInfo {
+                    user: "admin".to_string(),
+                    admin: true
+                },
+                UserInfo {
+                    user: "grafana".to_string(),
+                    admin: false
+                },
+            ]
+        );
+
+        let mut desired = BTreeMap::new();
+        desired.insert("metrics".to_string(), Privilege::Read);
+        desired.insert("logs".to_string(), Privilege::All);
+        desired.insert("new".to_string(), Privilege::Write);
+        client.sync_user_grants("grafana", &desired).await.unwrap();
+        assert!(matches!(
+            client.sync_user_grants("nobody", &desired).await,
+            Err(Error::Server { ref message, .. }) if message == "user not found"
+        ));
+
+        let requests = server.join().unwrap();
+        let statements: Vec<_> = requests[1..5]
+            .iter()
+            .map(|request| request_param(request, "q").unwrap())
+            .collect();
+        assert_eq!(
+            statements,
+            vec![
+                "SHOW GRANTS FOR \"grafana\"",
+                "Revoke ALL on \"old\" from \"grafana\"",
+                "Grant ALL on \"logs\" to \"grafana\"",
+                "Grant WRITE on \"new\" to \"grafana\"",
+            ]
+        );
+    });
+}
+
+#[test]
+fn kill_query_on_timeout() {
+    block_on(async {
+        let (url, server) = serve_responses(vec![
+            String::new(),
+            json_response(
+                r#"{"results":[{"statement_id":0,"series":[{"columns":["qid","query","database","duration","status"],"values":[[10,"SELECT * FROM cpu","test","2s","running"],[11,"SELECT * FROM cpu","other","50ms","running"],[12,"SELECT * FROM cpu","test","50ms","running"],[13,"SELECT * FROM mem","test","50ms","running"],[14,"SHOW QUERIES","","31µs","running"]]}]}]}"#,
+            ),
+            json_response(r#"{"results":[{"statement_id":0}]}"#),
+        ]);
+        let client = Client::new(url, "test").set_query_timeout(Duration::from_millis(100));
+        assert_eq!(client.get_query_timeout(), Some(Duration::from_millis(100)));
+
+        assert!(matches!(
+            client.query("select * from \"cpu\"", None).await,
+            Err(Error::Timeout(_))
+        ));
+
+        let requests = server.join().unwrap();
+        assert_eq!(request_param(&requests[1], "q").unwrap(), "SHOW QUERIES");
+        assert_eq!(request_param(&requests[2], "q").unwrap(), "Kill query 12");
+    });
+}
+
+#[test]
+fn kill_reformatted_query_on_timeout() {
+    block_on(async {
+        let (url, server) = serve_responses(vec![
+            String::new(),
+            json_response(
+                r#"{"results":[{"statement_id":0,"series":[{"columns":["qid","query","database","duration","status"],"values":[[20,"SELECT mean(usage) FROM cpu WHERE usage > 0.600 AND host = 'it\\'s' AND time > now() - 1h","test","50ms","running"],[21,"SELECT mean(usage) FROM cpu WHERE usage > 0.500 AND host = 'it\\'s' AND time > now() - 1h","test","50ms","running"]]}]}]}"#,
+            ),
+            json_response(r#"{"results":[{"statement_id":0}]}"#),
+        ]);
+        let client = Client::new(url, "test").set_query_timeout(Duration::from_millis(100));
+
+        assert!(matches!(
+            client
+                .query_with_params(
+                    "select mean(usage) from \"cpu\" where usage > 0.5 and host = $host and time > now() - 60m",
+                    &[("host", QueryParam::from("it's"))],
+                    None,
+                )
+                .await,
+            Err(Error::Timeout(_))
+        ));
+
+        let requests = server.join().unwrap();
+        assert_eq!(request_param(&requests[1], "q").unwrap(), "SHOW QUERIES");
+        assert_eq!(request_param(&requests[2], "q").unwrap(), "Kill query 21");
+    });
+}
+
+#[test]
+fn no_kill_when_ambiguous() {
+    block_on(async {
+        let (url, server) = serve_responses(vec![
+            String::new(),
+            json_response(
+                r#"{"results":[{"statement_id":0,"series":[{"columns":["qid","query","database","duration","status"],"values":[[12,"SELECT * FROM cpu","test","50ms","running"],[13,"SELECT * FROM cpu","test","20ms","running"]]}]}]}"#,
+            ),
+            json_response(r#"{"results":[{"statement_id":0,"error":"no such query id: 999"}]}"#),
+        ]);
+        let client = Client::new(url, "test").set_query_timeout(Duration::from_millis(100));
+
+        // never polled, never sent
+        drop(client.query_as::<serde_json::Value>("select * from cpu", None));
+        assert!(matches!(
+            client
+                .query_as::<serde_json::Value>("select * from cpu", None)
+                .await,
+            Err(Error::Timeout(_))
+        ));
+        // let the lookup find two candidates and give up
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(matches!(
+            client.kill_query(999).await,
+            Err(Error::Server { ref message, .. }) if message == "no such query id: 999"
+        ));
+
+        let requests = server.join().unwrap();
+        assert_eq!(
+            request_param(&requests[0], "q").unwrap(),
+            "select * from cpu"
+        );
+        assert_eq!(request_param(&requests[1], "q").unwrap(), "SHOW QUERIES");
+        assert_eq!(request_param(&requests[2], "q").unwrap(), "Kill query 999");
+    });
+}
+
+#[test]
+fn v2_client_write() {
+    block_on(async {
+        use influx_db_client::{RequestKind, V2Client};
+
+        let (url, server) = serve_responses(vec![
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n".to_string(),
+            json_response(r#"{"code":"not found","message":"bucket not found"}"#)
+                .replace("200 OK", "404 Not Found"),
+        ]);
+        let client = V2Client::new(url, "my org", "metrics").set_token("secret");
+
+        client
+            .write_point(
+                Point::new("cpu")
+                    .add_tag("host", "a")
+                    .add_field("usage", 0.5)
+                    .add_timestamp(1_500),
+                Some(Precision::Milliseconds),
+            )
+            .await
+            .unwrap();
+        let err = client
+            .write_point(Point::new("cpu").add_field("usage", 0.5), None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err,
+            Error::DataBaseDoesNotExist {
+                status: 404,
+                kind: RequestKind::Write,
+                message: "bucket not found".to_string(),
+            }
+        );
+        assert!(matches!(
+            client
+                .write_point(
+                    Point::new("cpu").add_field("usage", 0.5),
+                    Some(Precision::Hours)
+                )
+                .await,
+            Err(Error::InvalidArgument(_))
+        ));
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("POST /api/v2/write?"));
+        assert_eq!(request_param(&requests[0], "org").unwrap(), "my org");
+        assert_eq!(request_param(&requests[0], "bucket").unwrap(), "metrics");
+        assert_eq!(request_param(&requests[0], "precision").unwrap(), "ms");
+        assert_eq!(request_param(&requests[1], "precision").unwrap(), "s");
+        assert!(requests[0]
+            .to_lowercase()
+            .contains("authorization: token secret\r\n"));
+        assert!(requests[0].ends_with("\r\n\r\ncpu,host=a usage=0.5 1500\n"));
+    });
 }
@@ -0,0 +1,67 @@
+#![cfg(feature = "derive")]
+
+use influx_db_client::{InfluxPoint, Point, Value};
+use std::borrow::Cow;
+
+#[derive(InfluxPoint)]
+#[influx(measurement = "cpu")]
+struct Cpu {
+    #[influx(tag)]
+    host: String,
+    #[influx(tag, rename = "dc")]
+    datacenter: Option<String>,
+    usage: f64,
+    #[influx(field, rename = "load")]
+    load_average: Option<f64>,
+    #[influx(skip)]
+    #[allow(dead_code)]
+    sampled_by: String,
+    #[influx(timestamp)]
+    time: i64,
+}
+
+#[derive(InfluxPoint)]
+struct Request<'a> {
+    #[influx(tag)]
+    path: &'a str,
+    status: i64,
+    #[influx(timestamp)]
+    time: Option<i64>,
+}
+
+#[test]
+fn derive_point() {
+    let cpu = Cpu {
+        host: "server01".to_string(),
+        datacenter: None,
+        usage: 0.5,
+        load_average: Some(1.5),
+        sampled_by: "agent".to_string(),
+        time: 1_508_981_970,
+    };
+
+    let expected = Point::new("cpu")
+        .add_tag("host", "server01")
+        .add_field("usage", 0.5)
+        .add_field("load", 1.5)
+        .add_timestamp(1_508_981_970);
+
+    assert_eq!(Point::from(cpu), expected);
+}
+
+#[test]
+fn derive_point_with_lifetime() {
+    let path = String::from("/write");
+    let request = Request {
+        path: &path,
+        status: 204,
+        time: None,
+    };
+
+    let point: Point = request.into();
+
+    assert_eq!(point.measurement, "Request");
+    assert_eq!(point.tags["path"], Value::String(Cow::Borrowed("/write")));
+    assert_eq!(point.fields["status"], Value::Integer(204));
+    assert_eq!(point.timestamp, None);
+}